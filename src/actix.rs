@@ -0,0 +1,220 @@
+//! Middleware and extractor for [actix-web](https://actix.rs)
+//!
+//! Requires the `actix-web` feature.
+
+use crate::{ClientIp, ClientIpRejection, Decision, Resolver};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    HeaderMap, HeaderName, HeaderValue, FORWARDED, X_FORWARDED_FOR, X_FORWARDED_HOST,
+    X_FORWARDED_PROTO,
+};
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, ResponseError};
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Middleware that resolves the "real-ip" of every request once and stores it in the request extensions
+///
+/// The [`ClientIp`] is stored for handlers, together with the [`Decision`] for anything that needs the full report.
+/// The remote address is taken from the peer address of the connection, requests without peer address are passed
+/// on without resolving their address.
+///
+/// # Example
+///
+/// ```rust
+/// # use actix_web::{test, web, App, HttpRequest};
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::actix::RealIpMiddleware;
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// # #[actix_web::main]
+/// # async fn main() {
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app = test::init_service(
+///     App::new()
+///         .wrap(RealIpMiddleware::new(config).override_connection_info(true))
+///         .route("/", web::get().to(|ClientIp(ip): ClientIp| async move { ip.to_string() }))
+///         .route("/info", web::get().to(|request: HttpRequest| async move {
+///             let info = request.connection_info();
+///             format!("{} {}", info.realip_remote_addr().unwrap_or_default(), info.host())
+///         })),
+/// )
+/// .await;
+///
+/// let request = test::TestRequest::get()
+///     .uri("/")
+///     .peer_addr(SocketAddr::from(([10, 0, 0, 1], 46532)))
+///     .insert_header(("x-forwarded-for", "198.51.100.1, 192.0.2.1"))
+///     .to_request();
+/// assert_eq!("192.0.2.1", test::call_and_read_body(&app, request).await);
+///
+/// // actix itself would report the spoofed left-most address and host
+/// let request = test::TestRequest::get()
+///     .uri("/info")
+///     .peer_addr(SocketAddr::from(([10, 0, 0, 1], 46532)))
+///     .insert_header(("x-forwarded-for", "198.51.100.1, 192.0.2.1"))
+///     .insert_header(("x-forwarded-host", "evil.example, good.example"))
+///     .to_request();
+/// assert_eq!("192.0.2.1 good.example", test::call_and_read_body(&app, request).await);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RealIpMiddleware {
+    resolver: Arc<Resolver>,
+    override_connection_info: bool,
+}
+
+impl RealIpMiddleware {
+    /// Create the middleware resolving requests with a [`RealIpConfig`](crate::RealIpConfig) or [`Resolver`]
+    pub fn new(resolver: impl Into<Resolver>) -> Self {
+        RealIpMiddleware {
+            resolver: Arc::new(resolver.into()),
+            override_connection_info: false,
+        }
+    }
+
+    /// Make `ConnectionInfo::realip_remote_addr` report the resolved address, disabled by default
+    ///
+    /// The scheme and host of the `ConnectionInfo` are also taken from the trusted proxies, or from the request
+    /// itself when the client connected directly, so existing code using the `ConnectionInfo` can't be spoofed.
+    pub fn override_connection_info(mut self, enabled: bool) -> Self {
+        self.override_connection_info = enabled;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RealIpMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RealIpMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RealIpMiddlewareService {
+            service,
+            middleware: self.clone(),
+        }))
+    }
+}
+
+/// The service created by [`RealIpMiddleware`]
+#[derive(Debug)]
+pub struct RealIpMiddlewareService<S> {
+    service: S,
+    middleware: RealIpMiddleware,
+}
+
+impl<S, B> Service<ServiceRequest> for RealIpMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, mut request: ServiceRequest) -> Self::Future {
+        if let Some(remote) = request.peer_addr() {
+            let headers = to_http_headers(request.headers());
+            let decision = self
+                .middleware
+                .resolver
+                .resolve_with_report(&headers, remote);
+            if let Some(ip) = decision.ip {
+                if self.middleware.override_connection_info {
+                    override_connection_info(&mut request, ip, &decision);
+                }
+                request.extensions_mut().insert(ClientIp(ip));
+            }
+            request.extensions_mut().insert(decision);
+        }
+        self.service.call(request)
+    }
+}
+
+impl FromRequest for ClientIp {
+    type Error = ClientIpRejection;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let extensions = request.extensions();
+        let result = match (extensions.get::<ClientIp>(), extensions.get::<Decision>()) {
+            (Some(ip), _) => Ok(*ip),
+            (None, Some(decision)) => Err(ClientIpRejection::Unresolved(decision.error.clone())),
+            (None, None) if request.peer_addr().is_none() => {
+                Err(ClientIpRejection::MissingConnectInfo)
+            }
+            (None, None) => Err(ClientIpRejection::MissingConfig),
+        };
+        ready(result)
+    }
+}
+
+impl ResponseError for ClientIpRejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status().as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// Copy the headers of an actix request, which uses an older version of the `http` crate
+fn to_http_headers(headers: &HeaderMap) -> http::HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                http::HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+                http::HeaderValue::from_bytes(value.as_bytes()).ok()?,
+            ))
+        })
+        .collect()
+}
+
+const OVERRIDDEN_HEADERS: [HeaderName; 4] = [
+    FORWARDED,
+    X_FORWARDED_FOR,
+    X_FORWARDED_HOST,
+    X_FORWARDED_PROTO,
+];
+
+/// Cache a `ConnectionInfo` in the request that reports the resolved address
+///
+/// The `ConnectionInfo` can't be constructed directly, so it's created from a `forwarded` header describing the
+/// decision, after which the original headers are restored.
+fn override_connection_info(request: &mut ServiceRequest, ip: IpAddr, decision: &Decision) {
+    let mut forwarded = match ip {
+        IpAddr::V4(ip) => format!("for={}", ip),
+        IpAddr::V6(ip) => format!("for=\"[{}]\"", ip),
+    };
+    if let Some(proto) = &decision.proto {
+        forwarded.push_str(&format!(";proto={}", proto));
+    }
+    if let Some(host) = &decision.host {
+        forwarded.push_str(&format!(";host=\"{}\"", host));
+    }
+    let Ok(forwarded) = HeaderValue::from_str(&forwarded) else {
+        return;
+    };
+
+    let headers = &mut request.head_mut().headers;
+    let original: Vec<(HeaderName, HeaderValue)> = OVERRIDDEN_HEADERS
+        .iter()
+        .flat_map(|name| headers.remove(name).map(|value| (name.clone(), value)))
+        .collect();
+    headers.insert(FORWARDED, forwarded);
+
+    drop(request.connection_info());
+
+    let headers = &mut request.head_mut().headers;
+    headers.remove(FORWARDED);
+    for (name, value) in original {
+        headers.append(name, value);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::TrustedProxies;
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::io;
+
+/// The address ranges AWS publishes in [`ip-ranges.json`](https://docs.aws.amazon.com/vpc/latest/userguide/aws-ip-ranges.html)
+///
+/// The file can be downloaded once and parsed offline, or kept up to date with `RangeFeed::Aws` from the `fetch` feature.
+///
+/// Requires the `aws-ip-ranges` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::AwsIpRanges;
+/// #
+/// let ranges = AwsIpRanges::parse(r#"{
+///     "syncToken": "1728900000",
+///     "createDate": "2024-10-14-10-00-00",
+///     "prefixes": [
+///         {"ip_prefix": "3.5.140.0/22", "region": "ap-northeast-2", "service": "AMAZON", "network_border_group": "ap-northeast-2"},
+///         {"ip_prefix": "130.176.0.0/18", "region": "GLOBAL", "service": "CLOUDFRONT_ORIGIN_FACING", "network_border_group": "GLOBAL"}
+///     ],
+///     "ipv6_prefixes": [
+///         {"ipv6_prefix": "2600:9000:2000::/36", "region": "GLOBAL", "service": "CLOUDFRONT", "network_border_group": "GLOBAL"}
+///     ]
+/// }"#)?;
+///
+/// assert_eq!("1728900000", ranges.sync_token());
+/// let cloudfront = ranges.service("CLOUDFRONT_ORIGIN_FACING", None);
+/// assert!(cloudfront.contains(IpAddr::from([130, 176, 0, 1])));
+/// assert!(!cloudfront.contains(IpAddr::from([3, 5, 140, 1])));
+/// assert!(ranges.service("AMAZON", Some("us-east-1")).networks().is_empty());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AwsIpRanges {
+    #[serde(rename = "syncToken")]
+    sync_token: String,
+    prefixes: Vec<AwsPrefix>,
+    ipv6_prefixes: Vec<AwsPrefix>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct AwsPrefix {
+    #[serde(alias = "ipv6_prefix")]
+    ip_prefix: IpNet,
+    region: String,
+    service: String,
+}
+
+impl AwsIpRanges {
+    /// Parse the contents of `ip-ranges.json`, failing with [`io::ErrorKind::InvalidData`] for invalid contents
+    pub fn parse(json: &str) -> io::Result<Self> {
+        serde_json::from_str(json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// The publication time of the ranges, as unix timestamp
+    pub fn sync_token(&self) -> &str {
+        &self.sync_token
+    }
+
+    /// The ranges used by a service, optionally limited to a single region
+    ///
+    /// CloudFront connects to origins from the `CLOUDFRONT_ORIGIN_FACING` ranges, which are a subset of the
+    /// `CLOUDFRONT` ranges that also contain the addresses clients connect to.
+    pub fn service(&self, service: &str, region: Option<&str>) -> TrustedProxies {
+        self.prefixes
+            .iter()
+            .chain(&self.ipv6_prefixes)
+            .filter(|prefix| prefix.service.eq_ignore_ascii_case(service))
+            .filter(|prefix| {
+                region.map_or(true, |region| prefix.region.eq_ignore_ascii_case(region))
+            })
+            .map(|prefix| prefix.ip_prefix)
+            .collect()
+    }
+}
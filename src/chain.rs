@@ -0,0 +1,515 @@
+use crate::headers::{extract_via_header, normalize_host, normalize_proto, Element};
+use crate::reserved::{is_internal, is_private, is_reserved};
+use crate::trust::{ipv4_mapped, is_trusted_proxy};
+use crate::{
+    ConnectionInfo, Decision, EmptyElements, Fallback, ForwardedContext, ForwardingHeader, Hop,
+    MalformedElements, OnInvalid, Oversized, RealIpConfig, RealIpError, RealIpSource, Strategy,
+    TrustPolicy, UnknownNodes, ZoneIds,
+};
+use http::HeaderValue;
+use itertools::Either;
+use std::iter::once;
+use std::net::IpAddr;
+
+/// The forwarding chain of a request, with the trust status of every hop
+pub(crate) struct Chain<'a> {
+    config: &'a RealIpConfig,
+    header: Option<(&'a ForwardingHeader, &'a HeaderValue)>,
+    hops: Vec<Hop>,
+    /// Set when the request was rejected while building the chain
+    error: Option<RealIpError>,
+    /// Whether an oversized header value was truncated
+    truncated: bool,
+    /// Whether the chain ended at an untrusted node without address
+    unknown_client: bool,
+    /// Whether the request arrived over a unix socket, in which case the chain has no remote hop
+    unix_socket: bool,
+}
+
+impl<'a> Chain<'a> {
+    /// Build the chain from the first of the configured headers that is present and the remote address
+    pub(crate) fn new<S: RealIpSource + ?Sized>(
+        config: &'a RealIpConfig,
+        source: &'a S,
+        connection: ConnectionInfo,
+        trust: &(impl TrustPolicy + ?Sized),
+    ) -> Self {
+        let remote = connection.remote;
+        let header = if config.honors_headers_on(&connection) {
+            config
+                .headers()
+                .iter()
+                .filter(|header| config.accepts_header(header, remote.ip()))
+                .find_map(|header| Some((header, source.header(header.name()).next()?)))
+        } else {
+            None
+        };
+        // the hops are collected from the right, starting with the remote address, and reversed once all of them
+        // up to `max_hops` are parsed and checked for trust
+        let mut hops: Vec<Hop> = remote
+            .ip()
+            .map(|ip| Hop {
+                ip,
+                port: remote.port(),
+                proto: None,
+                host: None,
+                forwarded_port: None,
+                by: None,
+                zone: None,
+                trusted: false,
+                source_header: None,
+            })
+            .into_iter()
+            .collect();
+        let remote_hops = hops.len();
+        let mut error = None;
+        let mut truncated = false;
+        let mut unknown_client = false;
+        if let Some((header, _)) = header {
+            // a proxy that replaced x-forwarded-for can preserve the original chain, which continues to the left
+            let original = Some(&ForwardingHeader::XOriginalForwardedFor).filter(|_| {
+                *header == ForwardingHeader::XForwardedFor
+                    && remote.ip().is_some_and(|ip| {
+                        is_trusted_proxy(ip, config.original_forwarded_for_proxies())
+                    })
+            });
+            let values = source
+                .header(header.name())
+                .rev()
+                .map(move |value| (header, value))
+                .chain(original.into_iter().flat_map(|original| {
+                    source
+                        .header(original.name())
+                        .rev()
+                        .map(move |value| (original, value))
+                }));
+            'values: for (header, value) in values {
+                let malformed = |value: &HeaderValue| RealIpError::MalformedHeader {
+                    header: header.clone(),
+                    value: value.clone(),
+                };
+                let elements = match value.to_str() {
+                    Ok(mut value_str) => {
+                        match config.max_header_length() {
+                            Some(max) if value_str.len() > max => match config.oversized() {
+                                Oversized::Reject => {
+                                    error = Some(RealIpError::HeaderTooLarge {
+                                        header: header.clone(),
+                                    });
+                                    break;
+                                }
+                                Oversized::Truncate => {
+                                    value_str = truncate(value_str, max);
+                                    truncated = true;
+                                }
+                            },
+                            _ => {}
+                        }
+                        Either::Left(header.parse(value_str).rev())
+                    }
+                    Err(_) => Either::Right(once(Element::Malformed)),
+                };
+                for element in elements {
+                    match (element, config.empty_elements()) {
+                        (Element::Ip { .. }, _)
+                            if Some(hops.len() - remote_hops) == config.max_hops() =>
+                        {
+                            error = Some(RealIpError::TooManyHops);
+                            break 'values;
+                        }
+                        (
+                            Element::Ip {
+                                ip,
+                                port,
+                                protocol,
+                                host,
+                                by,
+                                zone,
+                            },
+                            _,
+                        ) => hops.push(Hop {
+                            ip,
+                            port,
+                            proto: protocol.and_then(normalize_proto),
+                            host: host.and_then(normalize_host),
+                            forwarded_port: None,
+                            by: by.map(String::from),
+                            zone: match config.zone_ids() {
+                                ZoneIds::Strip => None,
+                                ZoneIds::Preserve => zone.map(String::from),
+                            },
+                            trusted: false,
+                            source_header: Some(header.clone()),
+                        }),
+                        (Element::Empty, EmptyElements::Skip) => {}
+                        (Element::Empty, EmptyElements::Terminate) => break 'values,
+                        (Element::Empty, EmptyElements::Reject) => {
+                            error = Some(malformed(value));
+                            break 'values;
+                        }
+                        (Element::Malformed, _) => match config.malformed_elements() {
+                            MalformedElements::Skip => {}
+                            MalformedElements::Terminate => break 'values,
+                            MalformedElements::Reject => {
+                                error = Some(malformed(value));
+                                break 'values;
+                            }
+                        },
+                        (Element::Obfuscated(_) | Element::Unknown, _) => {
+                            match config.unknown_nodes() {
+                                UnknownNodes::Skip => {}
+                                UnknownNodes::Untrusted => {
+                                    unknown_client = true;
+                                    break 'values;
+                                }
+                                UnknownNodes::Reject => {
+                                    error = Some(RealIpError::UnknownNode);
+                                    break 'values;
+                                }
+                                UnknownNodes::Terminate => break 'values,
+                            }
+                        }
+                    }
+                }
+                if truncated {
+                    // anything to the left of a truncated value is discarded
+                    break;
+                }
+            }
+        }
+        // the proxy connected over a unix socket is implicitly trusted, but still counts as a hop
+        let offset = 1 - remote_hops;
+        for (index, hop) in hops.iter_mut().enumerate() {
+            let index = index + offset;
+            if config.canonicalize_ipv4_mapped() {
+                if let Some(ip) = ipv4_mapped(hop.ip) {
+                    hop.ip = ip.into();
+                }
+            }
+            hop.trusted = match config.strategy() {
+                _ if is_trusted_proxy(hop.ip, config.never_trusted()) => false,
+                Strategy::TrustedHopCount(count) => index < count,
+                _ => {
+                    trust.is_trusted(hop.ip, index)
+                        || is_trusted_proxy(hop.ip, config.internal_proxies())
+                        || (config.trust_private_hops() && is_private(hop.ip))
+                }
+            };
+        }
+        hops.reverse();
+
+        // x-forwarded-proto, x-forwarded-host and x-forwarded-port are aligned from the right with the forwarded
+        // addresses, the right-most value is the one appended by the proxy that also appended the right-most address
+        if let Some((header, _)) =
+            header.filter(|(header, _)| **header != ForwardingHeader::Forwarded)
+        {
+            let forwarded = hops.len() - remote_hops;
+            let from_header = |hop: &&mut Hop| hop.source_header.as_ref() == Some(header);
+            for (hop, proto) in hops[..forwarded]
+                .iter_mut()
+                .rev()
+                .filter(from_header)
+                .zip(list_values(source, X_FORWARDED_PROTO).rev())
+            {
+                hop.proto = normalize_proto(proto);
+            }
+            for (hop, host) in hops[..forwarded]
+                .iter_mut()
+                .rev()
+                .filter(from_header)
+                .zip(list_values(source, X_FORWARDED_HOST).rev())
+            {
+                hop.host = normalize_host(host);
+            }
+            for (hop, port) in hops[..forwarded]
+                .iter_mut()
+                .rev()
+                .filter(from_header)
+                .zip(list_values(source, X_FORWARDED_PORT).rev())
+            {
+                hop.forwarded_port = port.parse().ok();
+            }
+        }
+
+        if config.restrict_internal_clients() {
+            // a trusted proxy that isn't internal can't vouch for a client with an internal address,
+            // the chain ends at that proxy
+            for index in (0..hops.len().saturating_sub(1)).rev() {
+                let proxy = &hops[index + 1];
+                if !proxy.trusted {
+                    break;
+                }
+                if !is_trusted_proxy(proxy.ip, config.internal_proxies())
+                    && is_internal(hops[index].ip)
+                {
+                    hops.drain(..=index);
+                    break;
+                }
+            }
+        }
+
+        let mut chain = Chain {
+            config,
+            header,
+            hops,
+            error,
+            truncated,
+            unknown_client,
+            unix_socket: remote.is_unix_socket(),
+        };
+        if chain.error.is_none() && config.validate_consistency() {
+            chain.error = chain.check_consistency(source).err();
+        }
+        if chain.error.is_none() && !config.proxy_identities().is_empty() {
+            chain.error = chain.check_identities().err();
+        }
+        if chain.error.is_none() && config.reject_reserved_client() {
+            chain.error = match chain.resolve() {
+                Some(ip) if is_reserved(ip) => Some(RealIpError::ReservedClient(ip)),
+                _ => None,
+            };
+        }
+        chain
+    }
+
+    /// Check that all other configured headers that are present describe the same chain
+    fn check_consistency(&self, source: &(impl RealIpSource + ?Sized)) -> Result<(), RealIpError> {
+        let (header, _) = match self.header {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+        let remote = self.remote_index().map(|index| self.hops[index].ip);
+        let forwarded: Vec<IpAddr> = self.hops[..self.forwarded_len()]
+            .iter()
+            .map(|hop| hop.ip)
+            .collect();
+
+        for other in self
+            .config
+            .headers()
+            .iter()
+            .filter(|other| *other != header)
+            .filter(|other| self.config.accepts_header(other, remote))
+        {
+            if source.header(other.name()).next().is_some() {
+                let other_forwarded: Vec<IpAddr> = other
+                    .extract_all(source)
+                    .map(|ip| match ipv4_mapped(ip) {
+                        Some(mapped) if self.config.canonicalize_ipv4_mapped() => mapped.into(),
+                        _ => ip,
+                    })
+                    .collect();
+                let consistent = if *other == ForwardingHeader::XEnvoyExternalAddress {
+                    // envoy reports the client address it resolved itself
+                    let client = self.resolve();
+                    other_forwarded.iter().all(|ip| Some(*ip) == client)
+                } else if header.is_single_address() {
+                    forwarded.iter().all(|ip| other_forwarded.contains(ip))
+                } else if other.is_single_address() {
+                    other_forwarded.iter().all(|ip| forwarded.contains(ip))
+                } else {
+                    forwarded == other_forwarded
+                };
+                if !consistent {
+                    return Err(RealIpError::ConflictingHeaders {
+                        header: header.clone(),
+                        conflicting: other.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every `forwarded` element added by a trusted proxy names one of our proxy identities
+    fn check_identities(&self) -> Result<(), RealIpError> {
+        let identities = self.config.proxy_identities();
+        for (index, hop) in self.hops.iter().enumerate() {
+            let proxy_trusted = match self.hops.get(index + 1) {
+                Some(proxy) => proxy.trusted,
+                None => self.unix_socket,
+            };
+            let checked = proxy_trusted && hop.source_header == Some(ForwardingHeader::Forwarded);
+            let known = match &hop.by {
+                Some(by) => identities.contains(by),
+                None => false,
+            };
+            if checked && !known {
+                return Err(RealIpError::UnknownProxyIdentity(hop.by.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of hops that were read from the forwarding header
+    fn forwarded_len(&self) -> usize {
+        match self.remote_index() {
+            Some(remote) => remote,
+            None => self.hops.len(),
+        }
+    }
+
+    /// The index of the remote hop, `None` for requests arriving over a unix socket
+    fn remote_index(&self) -> Option<usize> {
+        if self.unix_socket {
+            None
+        } else {
+            Some(self.hops.len() - 1)
+        }
+    }
+
+    /// The index of the hop selected by the configured strategy
+    fn client_index(&self) -> Option<usize> {
+        let index = match self.config.strategy() {
+            Strategy::RightmostUntrusted | Strategy::TrustedHopCount(_) => {
+                self.hops.iter().rposition(|hop| !hop.trusted)
+            }
+            Strategy::LeftmostHeaderValue => Some(0),
+            Strategy::RightmostHeaderValue => Some(self.forwarded_len().saturating_sub(1)),
+        };
+        index.filter(|index| *index < self.hops.len())
+    }
+
+    /// The address and trust index of the right-most untrusted hop, the hop that ends the chain
+    pub(crate) fn rightmost_untrusted(&self) -> Option<(IpAddr, usize)> {
+        let position = self.hops.iter().rposition(|hop| !hop.trusted)?;
+        let index = self.hops.len() - 1 - position + usize::from(self.unix_socket);
+        Some((self.hops[position].ip, index))
+    }
+
+    /// Find the client address in the chain
+    pub(crate) fn resolve(&self) -> Option<IpAddr> {
+        self.resolve_hop().map(|hop| hop.ip)
+    }
+
+    /// Find the hop of the client in the chain
+    pub(crate) fn resolve_hop(&self) -> Option<&Hop> {
+        self.resolve_index().map(|index| &self.hops[index])
+    }
+
+    /// Find the index of the client hop in the chain
+    fn resolve_index(&self) -> Option<usize> {
+        let remote = self.remote_index();
+        if self.error.is_some() {
+            return match self.config.on_invalid() {
+                OnInvalid::Reject => None,
+                OnInvalid::UseRemote => remote,
+            };
+        }
+        match self.client_index() {
+            Some(index) => Some(index),
+            // the client is the unknown node to the left of the trusted hops
+            None if self.unknown_client => None,
+            // all hops were trusted
+            None => match self.config.fallback() {
+                Fallback::FirstHop => Some(0).filter(|_| !self.hops.is_empty()),
+                Fallback::Remote => remote,
+                Fallback::None => None,
+            },
+        }
+    }
+
+    /// Find the client address, failing if the chain can't be fully verified
+    pub(crate) fn try_resolve(&self) -> Result<IpAddr, RealIpError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        let remote = self.remote_index().map(|index| &self.hops[index]);
+        let strategy = self.config.strategy();
+        let header_strategy = matches!(
+            strategy,
+            Strategy::LeftmostHeaderValue | Strategy::RightmostHeaderValue
+        );
+        match (self.header, remote) {
+            (None, Some(remote)) if !remote.trusted && !header_strategy => return Ok(remote.ip),
+            (None, _) => return Err(RealIpError::NoForwardingHeader),
+            (Some((header, value)), _) if self.forwarded_len() == 0 && !self.unknown_client => {
+                return Err(RealIpError::MalformedHeader {
+                    header: header.clone(),
+                    value: value.clone(),
+                })
+            }
+            (Some(_), _) => {}
+        }
+
+        match self.client_index() {
+            None if self.unknown_client => Err(RealIpError::UnknownNode),
+            None => Err(RealIpError::AllHopsTrusted),
+            Some(index) if index == 0 || strategy != Strategy::RightmostUntrusted => {
+                Ok(self.hops[index].ip)
+            }
+            Some(index) => Err(RealIpError::UntrustedProxyInChain(self.hops[index].ip)),
+        }
+    }
+
+    pub(crate) fn into_hops(self) -> Vec<Hop> {
+        self.hops
+    }
+
+    pub(crate) fn into_context(mut self) -> Option<ForwardedContext> {
+        let index = self.resolve_index()?;
+        let proxies = self.hops.split_off(index + 1);
+        let client = self.hops.pop()?;
+        Some(ForwardedContext {
+            ip: client.ip,
+            port: client.port,
+            proto: client.proto,
+            host: client.host,
+            forwarded_port: client.forwarded_port,
+            proxies,
+        })
+    }
+
+    pub(crate) fn into_decision<S: RealIpSource + ?Sized>(self, source: &S) -> Decision {
+        let hop = self.resolve_hop();
+        let proto = hop.and_then(|hop| hop.proto.clone());
+        let host = hop.and_then(|hop| hop.host.clone());
+        let forwarded_port = hop.and_then(|hop| hop.forwarded_port);
+        Decision {
+            ip: self.resolve(),
+            proto,
+            host,
+            forwarded_port,
+            header: self.header.map(|(header, _)| header.clone()),
+            hops: self.hops,
+            error: self.error,
+            truncated: self.truncated,
+            via: source
+                .header(VIA)
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(extract_via_header)
+                .collect(),
+        }
+    }
+}
+
+/// Keep the last `max` bytes of a header value, dropping the partial left-most element
+fn truncate(value: &str, max: usize) -> &str {
+    // header values that are valid strings only contain visible ascii
+    let tail = &value[value.len() - max..];
+    if value.as_bytes()[value.len() - max - 1] == b',' {
+        // the cut is between two elements, so the tail only contains complete elements
+        return tail;
+    }
+    match tail.find(',') {
+        Some(index) => &tail[index + 1..],
+        None => "",
+    }
+}
+
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+const X_FORWARDED_PORT: &str = "x-forwarded-port";
+const VIA: &str = "via";
+
+/// The trimmed, comma separated values of all instances of a header
+fn list_values<'a, S: RealIpSource + ?Sized>(
+    source: &'a S,
+    name: &str,
+) -> impl DoubleEndedIterator<Item = &'a str> {
+    source
+        .header(name)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
@@ -0,0 +1,66 @@
+use crate::RealIpError;
+use http::StatusCode;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+
+/// The "real-ip" of a request, stored in the request extensions by the middleware of the web framework integrations
+///
+/// The extension is only added when the address could be determined.
+/// With the `axum`, `actix-web`, `rocket`, `poem` and `ntex` features this is also an extractor or request guard,
+/// which fails with a [`ClientIpRejection`] when the address can't be determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientIp(pub IpAddr);
+
+impl From<ClientIp> for IpAddr {
+    fn from(ip: ClientIp) -> Self {
+        ip.0
+    }
+}
+
+/// The reason a client ip extractor failed
+///
+/// Requests that are rejected because their address can't be determined are answered with `400 Bad Request`,
+/// a missing setup is answered with `500 Internal Server Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClientIpRejection {
+    /// The address of the request couldn't be determined, with the reason if the forwarding chain was rejected
+    Unresolved(Option<RealIpError>),
+    /// The remote address of the connection isn't known, for axum the server has to be started with
+    /// `real_ip_make_service` or `into_make_service_with_connect_info`
+    MissingConnectInfo,
+    /// No configuration was found for the request, the middleware of the framework wasn't added
+    MissingConfig,
+}
+
+impl ClientIpRejection {
+    /// The status code the request is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ClientIpRejection::Unresolved(_) => StatusCode::BAD_REQUEST,
+            ClientIpRejection::MissingConnectInfo | ClientIpRejection::MissingConfig => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl Display for ClientIpRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientIpRejection::Unresolved(Some(error)) => {
+                write!(f, "couldn't determine client address: {}", error)
+            }
+            ClientIpRejection::Unresolved(None) => write!(f, "couldn't determine client address"),
+            ClientIpRejection::MissingConnectInfo => {
+                write!(f, "request has no connection info")
+            }
+            ClientIpRejection::MissingConfig => {
+                write!(f, "no real-ip configuration added to the request")
+            }
+        }
+    }
+}
+
+impl Error for ClientIpRejection {}
@@ -0,0 +1,1550 @@
+use crate::chain::Chain;
+use crate::express::TrustProxy;
+use crate::source::cached_decision;
+use crate::trust::{is_trusted_proxy, ProxyList, SharedTrustPolicy};
+use crate::{
+    ClientAddr, ConnectionInfo, Decision, ForwardedContext, ForwardingHeader, Hop,
+    ParseTrustProxyError, RealIpError, RealIpSource, RequestExtensions, TrustPolicy,
+    DEFAULT_HEADERS,
+};
+use http::HeaderMap;
+use ipnet::IpNet;
+use std::borrow::Cow;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// What to return when every hop in the chain is a trusted proxy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Fallback {
+    /// Return the first (left-most) hop of the chain, this matches the behavior of [`real_ip`](crate::real_ip)
+    #[default]
+    FirstHop,
+    /// Return the address of the incoming connection
+    Remote,
+    /// Don't return any address
+    None,
+}
+
+/// What to return when a request is rejected because of invalid forwarding headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OnInvalid {
+    /// Don't return any address
+    #[default]
+    Reject,
+    /// Ignore the forwarding headers and return the address of the incoming connection
+    UseRemote,
+}
+
+/// How empty elements in a forwarding header are handled, for example in `192.0.2.1,, 10.0.0.1` or `192.0.2.1,`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EmptyElements {
+    /// Ignore the empty element
+    #[default]
+    Skip,
+    /// Reject the request with [`RealIpError::MalformedHeader`]
+    Reject,
+    /// End the chain at the empty element, only the elements to the right of it are used
+    Terminate,
+}
+
+/// How elements of a forwarding header that aren't a valid address are handled, for example `not-an-ip` in
+/// `192.0.2.1, not-an-ip`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum MalformedElements {
+    /// Ignore the element
+    #[default]
+    Skip,
+    /// Reject the request with [`RealIpError::MalformedHeader`]
+    Reject,
+    /// End the chain at the malformed element, only the elements to the right of it are used
+    Terminate,
+}
+
+/// How header values longer than the configured maximum length are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Oversized {
+    /// Reject the request with [`RealIpError::HeaderTooLarge`]
+    #[default]
+    Reject,
+    /// Only use the right-most part of the header value that fits within the maximum length.
+    ///
+    /// The partial element at the start of the truncated value, and any earlier header instances, are ignored.
+    /// Truncation is reported in [`Decision::truncated`].
+    Truncate,
+}
+
+/// How `unknown` and obfuscated nodes like `_hidden` in a `forwarded` header are handled
+///
+/// These nodes don't have an address that can be checked against the trusted proxies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum UnknownNodes {
+    /// Ignore the node, the hop to the left of it is used as if it was directly connected to the next proxy
+    #[default]
+    Skip,
+    /// Treat the node as an untrusted hop, ending the chain.
+    ///
+    /// If all hops to the right of the node are trusted, the node is the client and no address is returned.
+    Untrusted,
+    /// Reject the request with [`RealIpError::UnknownNode`]
+    Reject,
+    /// End the chain at the node, only the elements to the right of it are used
+    Terminate,
+}
+
+/// How the zone id of IPv6 addresses in a forwarding header is handled, for example `eth0` in `fe80::1%eth0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum ZoneIds {
+    /// Remove the zone id and only use the address
+    #[default]
+    Strip,
+    /// Keep the zone id in [`Hop::zone`](crate::Hop::zone)
+    Preserve,
+}
+
+/// How the client address is selected from the forwarding chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Strategy {
+    /// Use the right-most hop that isn't a trusted proxy
+    #[default]
+    RightmostUntrusted,
+    /// Use the left-most address from the forwarding header, without validating trusted proxies.
+    ///
+    /// This is only safe if every request is guaranteed to pass through a proxy that overwrites the header.
+    LeftmostHeaderValue,
+    /// Use the right-most address from the forwarding header, without validating trusted proxies.
+    ///
+    /// This is only safe if every request is guaranteed to pass through a proxy that appends to the header.
+    RightmostHeaderValue,
+    /// Trust a fixed number of hops from the right of the chain, regardless of their address.
+    ///
+    /// With `TrustedHopCount(0)` the remote address of the request is used, with `TrustedHopCount(1)` the
+    /// right-most address from the forwarding header, etc.
+    /// This is useful when the number of proxies in front of the application is known but their addresses aren't.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, Strategy};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .strategy(Strategy::TrustedHopCount(2))
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "198.51.100.1, 192.0.2.1, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    TrustedHopCount(usize),
+}
+
+/// Configuration for resolving the "real-ip" of a request.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{Fallback, ForwardingHeader, IpNet, RealIpConfig};
+/// #
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .headers([ForwardingHeader::XForwardedFor])
+///     .fallback(Fallback::Remote)
+///     .build();
+///
+/// let incoming_ip = IpAddr::from([10, 0, 0, 1]);
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, incoming_ip));
+/// ```
+///
+/// With the `serde` feature the configuration can be deserialized as part of the configuration of an application.
+/// Networks are written in cidr notation and every setting that is left out uses its default value.
+/// A custom [`TrustPolicy`] can't be serialized and is skipped.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::RealIpConfig;
+/// #
+/// let config: RealIpConfig = serde_json::from_str(r#"{
+///     "trusted_proxies": ["10.0.0.0/8", "203.0.113.10"],
+///     "headers": ["x-forwarded-for"],
+///     "strategy": "rightmost_untrusted",
+///     "max_hops": 10
+/// }"#).unwrap();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct RealIpConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    trusted_proxies: Vec<IpNet>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    excluded_proxies: Vec<IpNet>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    never_trusted: Vec<IpNet>,
+    headers: Cow<'static, [ForwardingHeader]>,
+    fallback: Fallback,
+    strategy: Strategy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    trust_policy: Option<SharedTrustPolicy>,
+    validate_consistency: bool,
+    malformed_elements: MalformedElements,
+    empty_elements: EmptyElements,
+    max_hops: Option<usize>,
+    max_header_length: Option<usize>,
+    oversized: Oversized,
+    on_invalid: OnInvalid,
+    proxy_identities: Vec<String>,
+    trusted_listeners: Vec<SocketAddr>,
+    require_tls: bool,
+    canonicalize_ipv4_mapped: bool,
+    zone_ids: ZoneIds,
+    unknown_nodes: UnknownNodes,
+    reject_reserved_client: bool,
+    trust_private_hops: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    internal_proxies: Vec<IpNet>,
+    restrict_internal_clients: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    cloudflare_proxies: Vec<IpNet>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::networks"))]
+    original_forwarded_for_proxies: Vec<IpNet>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::header_proxies"))]
+    header_proxies: Vec<(ForwardingHeader, Vec<IpNet>)>,
+}
+
+impl Default for RealIpConfig {
+    fn default() -> Self {
+        RealIpConfig {
+            trusted_proxies: Vec::new(),
+            excluded_proxies: Vec::new(),
+            never_trusted: Vec::new(),
+            headers: Cow::Borrowed(&DEFAULT_HEADERS),
+            fallback: Fallback::default(),
+            strategy: Strategy::default(),
+            trust_policy: None,
+            validate_consistency: false,
+            malformed_elements: MalformedElements::default(),
+            empty_elements: EmptyElements::default(),
+            max_hops: None,
+            max_header_length: None,
+            oversized: Oversized::default(),
+            on_invalid: OnInvalid::default(),
+            proxy_identities: Vec::new(),
+            trusted_listeners: Vec::new(),
+            require_tls: false,
+            canonicalize_ipv4_mapped: false,
+            zone_ids: ZoneIds::default(),
+            unknown_nodes: UnknownNodes::default(),
+            reject_reserved_client: false,
+            trust_private_hops: false,
+            internal_proxies: Vec::new(),
+            restrict_internal_clients: false,
+            cloudflare_proxies: Vec::new(),
+            original_forwarded_for_proxies: Vec::new(),
+            header_proxies: Vec::new(),
+        }
+    }
+}
+
+impl RealIpConfig {
+    /// Create a builder for the configuration
+    pub fn builder() -> RealIpConfigBuilder {
+        RealIpConfigBuilder::default()
+    }
+
+    /// The configured trusted proxies
+    pub fn trusted_proxies(&self) -> &[IpNet] {
+        &self.trusted_proxies
+    }
+
+    /// The networks that are excluded from the trusted proxies
+    pub fn excluded_proxies(&self) -> &[IpNet] {
+        &self.excluded_proxies
+    }
+
+    /// Replace the trusted proxies, keeping the rest of the configuration
+    #[cfg(feature = "arc-swap")]
+    pub(crate) fn set_trusted_proxies(&mut self, trusted_proxies: Vec<IpNet>) {
+        self.trusted_proxies = trusted_proxies;
+    }
+
+    /// The networks that are never trusted, regardless of how trust is configured
+    pub fn never_trusted(&self) -> &[IpNet] {
+        &self.never_trusted
+    }
+
+    /// The headers that are checked, in order of preference
+    pub fn headers(&self) -> &[ForwardingHeader] {
+        &self.headers
+    }
+
+    /// The configured fallback behavior
+    pub fn fallback(&self) -> Fallback {
+        self.fallback
+    }
+
+    /// The configured strategy for selecting the client address
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Get the "real-ip" of an incoming request.
+    ///
+    /// When resolving a large number of requests, use a [`Resolver`](crate::Resolver) instead.
+    pub fn resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.chain(request, connection).resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request from its headers.
+    pub fn resolve_headers(
+        &self,
+        headers: &HeaderMap,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.chain(headers, connection).resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request, including the client port if it's provided by the forwarding header.
+    pub fn resolve_with_port(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ClientAddr> {
+        self.chain(request, connection)
+            .resolve_hop()
+            .map(Hop::client_addr)
+    }
+
+    /// Get the "real-ip" of an incoming request, or the reason it couldn't be determined.
+    ///
+    /// See [`try_real_ip`](crate::try_real_ip) for details on when this fails.
+    pub fn try_resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Result<IpAddr, RealIpError> {
+        self.chain(request, connection).try_resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request, with a report of how it was determined.
+    pub fn resolve_with_report(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Decision {
+        self.chain(request, connection).into_decision(request)
+    }
+
+    /// Get the report of how the "real-ip" of an incoming request was determined, caching it in the
+    /// [`Extensions`](http::Extensions) of the request.
+    ///
+    /// When a decision is already cached in the request it is returned without parsing the headers again,
+    /// even if it was determined by a different configuration.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Decision, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .build();
+    ///
+    /// let mut request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// let decision = config.resolve_cached(&mut request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), decision.ip);
+    ///
+    /// // later handlers can use the cached decision
+    /// let decision = Decision::cached(request.extensions()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), decision.ip);
+    /// ```
+    pub fn resolve_cached<'r, R: RequestExtensions>(
+        &self,
+        request: &'r mut R,
+        connection: impl Into<ConnectionInfo>,
+    ) -> &'r Decision {
+        cached_decision(request, |request| {
+            self.resolve_with_report(request, connection)
+        })
+    }
+
+    /// Get the "real-ip" of an incoming request, together with the protocol, host and proxies reported by the
+    /// forwarding headers.
+    pub fn resolve_context(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ForwardedContext> {
+        self.chain(request, connection).into_context()
+    }
+
+    /// Whether all present forwarding headers are required to describe the same chain
+    pub fn validate_consistency(&self) -> bool {
+        self.validate_consistency
+    }
+
+    /// Whether requests with malformed elements in the forwarding header are rejected
+    pub fn strict(&self) -> bool {
+        self.malformed_elements == MalformedElements::Reject
+    }
+
+    /// How malformed elements in the forwarding header are handled
+    pub fn malformed_elements(&self) -> MalformedElements {
+        self.malformed_elements
+    }
+
+    /// How empty elements in the forwarding header are handled
+    pub fn empty_elements(&self) -> EmptyElements {
+        self.empty_elements
+    }
+
+    /// The maximum number of hops read from the forwarding header
+    pub fn max_hops(&self) -> Option<usize> {
+        self.max_hops
+    }
+
+    /// The maximum length in bytes of a forwarding header value
+    pub fn max_header_length(&self) -> Option<usize> {
+        self.max_header_length
+    }
+
+    /// How header values longer than the maximum length are handled
+    pub fn oversized(&self) -> Oversized {
+        self.oversized
+    }
+
+    /// What is returned when a request is rejected because of invalid forwarding headers
+    pub fn on_invalid(&self) -> OnInvalid {
+        self.on_invalid
+    }
+
+    /// The identities our own proxies use in the `by` parameter of the `forwarded` header
+    pub fn proxy_identities(&self) -> &[String] {
+        &self.proxy_identities
+    }
+
+    /// The local addresses of the listeners on which forwarding headers are honored, empty for all listeners
+    pub fn trusted_listeners(&self) -> &[SocketAddr] {
+        &self.trusted_listeners
+    }
+
+    /// Whether forwarding headers are only honored on tls connections
+    pub fn require_tls(&self) -> bool {
+        self.require_tls
+    }
+
+    /// Whether IPv4-mapped IPv6 addresses in the chain are converted to IPv4 addresses
+    pub fn canonicalize_ipv4_mapped(&self) -> bool {
+        self.canonicalize_ipv4_mapped
+    }
+
+    /// How the zone id of IPv6 addresses in a forwarding header is handled
+    pub fn zone_ids(&self) -> ZoneIds {
+        self.zone_ids
+    }
+
+    /// How `unknown` and obfuscated nodes in the forwarding header are handled
+    pub fn unknown_nodes(&self) -> UnknownNodes {
+        self.unknown_nodes
+    }
+
+    /// Whether requests are rejected when the resolved client address is in reserved address space
+    pub fn reject_reserved_client(&self) -> bool {
+        self.reject_reserved_client
+    }
+
+    /// Whether hops in private address space are trusted in addition to the trusted proxies
+    pub fn trust_private_hops(&self) -> bool {
+        self.trust_private_hops
+    }
+
+    /// The internal proxies, trusted proxies that are allowed to report clients with an internal address
+    pub fn internal_proxies(&self) -> &[IpNet] {
+        &self.internal_proxies
+    }
+
+    /// Whether clients with an internal address are only accepted when they are reported by an internal proxy
+    pub fn restrict_internal_clients(&self) -> bool {
+        self.restrict_internal_clients
+    }
+
+    /// The Cloudflare ranges the `cf-connecting-ip` header is accepted from
+    pub fn cloudflare_proxies(&self) -> &[IpNet] {
+        &self.cloudflare_proxies
+    }
+
+    /// The proxies that preserve the original `x-forwarded-for` chain in the `x-original-forwarded-for` header
+    pub fn original_forwarded_for_proxies(&self) -> &[IpNet] {
+        &self.original_forwarded_for_proxies
+    }
+
+    /// The proxies a header is accepted from, if it is limited to specific proxies
+    ///
+    /// In configuration files these are a map from the header name to its proxies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config: RealIpConfig = serde_json::from_str(r#"{
+    ///     "headers": ["x-real-ip", "forwarded"],
+    ///     "header_proxies": {"x-real-ip": ["10.0.0.1"]}
+    /// }"#).unwrap();
+    /// assert_eq!(
+    ///     Some(&[IpAddr::from([10, 0, 0, 1]).into()][..]),
+    ///     config.header_proxies(&ForwardingHeader::XRealIp)
+    /// );
+    /// assert_eq!(None, config.header_proxies(&ForwardingHeader::Forwarded));
+    /// # }
+    /// ```
+    pub fn header_proxies(&self, header: &ForwardingHeader) -> Option<&[IpNet]> {
+        self.header_proxies
+            .iter()
+            .find(|(scoped, _)| scoped == header)
+            .map(|(_, proxies)| proxies.as_slice())
+    }
+
+    /// Whether the header is accepted from the remote address
+    pub(crate) fn accepts_header(&self, header: &ForwardingHeader, remote: Option<IpAddr>) -> bool {
+        let scoped = match self.header_proxies(header) {
+            Some(proxies) => remote.is_some_and(|ip| is_trusted_proxy(ip, proxies)),
+            None => true,
+        };
+        scoped
+            && match header {
+                ForwardingHeader::CfConnectingIp => {
+                    remote.is_some_and(|ip| is_trusted_proxy(ip, &self.cloudflare_proxies))
+                }
+                _ => true,
+            }
+    }
+
+    /// Whether the forwarding headers of requests arriving on the connection are honored
+    pub(crate) fn honors_headers_on(&self, connection: &ConnectionInfo) -> bool {
+        let listener_trusted = self.trusted_listeners.is_empty()
+            || connection
+                .local_addr
+                .is_some_and(|addr| self.trusted_listeners.contains(&addr));
+        listener_trusted && (connection.tls || !self.require_tls)
+    }
+
+    /// The custom trust policy, if configured
+    pub(crate) fn trust_policy(&self) -> Option<&(dyn TrustPolicy + Send + Sync)> {
+        self.trust_policy.as_ref().map(|policy| &*policy.0)
+    }
+
+    pub(crate) fn chain<'a, S: RealIpSource + ?Sized>(
+        &'a self,
+        source: &'a S,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Chain<'a> {
+        let proxies = ProxyList {
+            trusted: &self.trusted_proxies,
+            excluded: &self.excluded_proxies,
+        };
+        let trust: &dyn TrustPolicy = match self.trust_policy() {
+            Some(policy) => policy,
+            None => &proxies,
+        };
+        Chain::new(self, source, connection.into(), trust)
+    }
+}
+
+/// Builder for [`RealIpConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct RealIpConfigBuilder {
+    config: RealIpConfig,
+}
+
+impl RealIpConfigBuilder {
+    /// Add a trusted proxy
+    pub fn trusted_proxy(mut self, proxy: IpNet) -> Self {
+        self.config.trusted_proxies.push(proxy);
+        self
+    }
+
+    /// Add a list of trusted proxies
+    pub fn trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        self.config.trusted_proxies.extend(proxies);
+        self
+    }
+
+    /// Accept the `cf-connecting-ip` header from Cloudflare's ranges.
+    ///
+    /// Cloudflare replaces the header with the address of the client, but a client connecting directly can send
+    /// any value. The header is only used when the remote address of the request is within one of these ranges,
+    /// other requests are resolved as if the header wasn't present.
+    /// The header still has to be enabled with [`headers`](Self::headers).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig, TrustedProxies};
+    /// #
+    /// let cloudflare = "173.245.48.0/20, 2400:cb00::/32".parse::<TrustedProxies>().unwrap();
+    /// let config = RealIpConfig::builder()
+    ///     .headers([ForwardingHeader::CfConnectingIp, ForwardingHeader::XForwardedFor])
+    ///     .trusted_proxies(cloudflare.clone())
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .cloudflare_proxies(cloudflare)
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("cf-connecting-ip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([173, 245, 48, 1])));
+    ///
+    /// // a trusted proxy that isn't Cloudflare passed on the header from the client
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn cloudflare_proxies(mut self, proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        self.config.cloudflare_proxies.extend(proxies);
+        self
+    }
+
+    /// Only accept a header when the remote address of the request is one of these proxies.
+    ///
+    /// This allows each header to be trusted from a different set of proxies, requests from other addresses
+    /// are resolved as if the header wasn't present. Calling this multiple times for the same header adds to its proxies.
+    /// The hops in the chain are still trusted according to the [trusted proxies](Self::trusted_proxies),
+    /// and the header still has to be enabled with [`headers`](Self::headers).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .headers([ForwardingHeader::XRealIp, ForwardingHeader::Forwarded])
+    ///     .trusted_proxy("10.0.0.0/8".parse().unwrap())
+    ///     .header_proxies(ForwardingHeader::XRealIp, [IpAddr::from([10, 0, 0, 1]).into()])
+    ///     .header_proxies(ForwardingHeader::Forwarded, ["10.0.0.0/8".parse().unwrap()])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("x-real-ip", "192.0.2.1")
+    ///     .header("forwarded", "for=198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 2])));
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 1])), config.resolve(&request, IpAddr::from([203, 0, 113, 1])));
+    /// ```
+    pub fn header_proxies(
+        mut self,
+        header: ForwardingHeader,
+        proxies: impl IntoIterator<Item = IpNet>,
+    ) -> Self {
+        match self
+            .config
+            .header_proxies
+            .iter_mut()
+            .find(|(scoped, _)| *scoped == header)
+        {
+            Some((_, existing)) => existing.extend(proxies),
+            None => self
+                .config
+                .header_proxies
+                .push((header, proxies.into_iter().collect())),
+        }
+        self
+    }
+
+    /// Continue the `x-forwarded-for` chain into the `x-original-forwarded-for` header for requests from these proxies.
+    ///
+    /// Ingress controllers that replace the `x-forwarded-for` header can preserve the original header as
+    /// `x-original-forwarded-for`, the hops in the original header are added to the left of the hops in
+    /// `x-forwarded-for` and are trusted like the other hops.
+    /// The original header is only used when the remote address of the request is one of these proxies,
+    /// since any other client can send the header.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .headers([ForwardingHeader::XForwardedFor])
+    ///     .trusted_proxy("10.0.0.0/8".parse().unwrap())
+    ///     // the ingress controller
+    ///     .original_forwarded_for_proxies([IpAddr::from([10, 0, 0, 1]).into()])
+    ///     .build();
+    ///
+    /// // the ingress replaced the header with the address of the load balancer in front of it
+    /// let request = Request::builder()
+    ///     .header("x-forwarded-for", "10.1.0.5")
+    ///     .header("x-original-forwarded-for", "192.0.2.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// // the original header isn't used when it is sent by another proxy
+    /// assert_eq!(Some(IpAddr::from([10, 1, 0, 5])), config.resolve(&request, IpAddr::from([10, 0, 0, 2])));
+    /// ```
+    pub fn original_forwarded_for_proxies(
+        mut self,
+        proxies: impl IntoIterator<Item = IpNet>,
+    ) -> Self {
+        self.config.original_forwarded_for_proxies.extend(proxies);
+        self
+    }
+
+    /// Exclude a network from the trusted proxies.
+    ///
+    /// Addresses within an excluded network aren't trusted, even if they are within one of the trusted networks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{IpNet, RealIpConfig};
+    /// #
+    /// // trust 10.0.0.0/8, except 10.13.0.0/16
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8))
+    ///     .exclude_proxy(IpNet::new_assert(IpAddr::from([10, 13, 0, 0]), 16))
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 12, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([10, 13, 0, 1])), config.resolve(&request, IpAddr::from([10, 13, 0, 1])));
+    /// ```
+    pub fn exclude_proxy(mut self, network: IpNet) -> Self {
+        self.config.excluded_proxies.push(network);
+        self
+    }
+
+    /// Exclude a list of networks from the trusted proxies
+    pub fn exclude_proxies(mut self, networks: impl IntoIterator<Item = IpNet>) -> Self {
+        self.config.excluded_proxies.extend(networks);
+        self
+    }
+
+    /// Never trust hops within a network.
+    ///
+    /// Unlike [`exclude_proxy`](Self::exclude_proxy) this is checked before any other trust configuration, so it also
+    /// overrides a custom [`TrustPolicy`], [`trust_private_hops`](Self::trust_private_hops) and
+    /// [`Strategy::TrustedHopCount`](crate::Strategy::TrustedHopCount).
+    /// This is useful when a single host inside the proxy network is known to be compromised.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, Strategy};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .strategy(Strategy::TrustedHopCount(2))
+    ///     .never_trust(IpAddr::from([10, 0, 0, 66]).into())
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.0.0.20").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.0.0.66").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 0, 0, 66])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn never_trust(mut self, network: IpNet) -> Self {
+        self.config.never_trusted.push(network);
+        self
+    }
+
+    /// Configure trust the same way as the `trust proxy` setting of Express.js.
+    ///
+    /// The setting is one of
+    ///
+    /// - `true`, trusting every hop and using the left-most address from the header
+    /// - `false`, trusting no hops and using the remote address
+    /// - a number of trusted hops, see [`Strategy::TrustedHopCount`](crate::Strategy::TrustedHopCount)
+    /// - a comma separated list of addresses, networks and the names `loopback`, `linklocal` and `uniquelocal`
+    ///
+    /// Like Express, only the `x-forwarded-for` header is used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ParseTrustProxyError, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .express_trust_proxy("loopback, 10.0.0.0/255.0.0.0")?
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([127, 0, 0, 1])));
+    ///
+    /// let config = RealIpConfig::builder().express_trust_proxy("1")?.build();
+    /// assert_eq!(Some(IpAddr::from([10, 1, 0, 3])), config.resolve(&request, IpAddr::from([127, 0, 0, 1])));
+    ///
+    /// assert!(RealIpConfig::builder().express_trust_proxy("loopback, localhost").is_err());
+    /// # Ok::<(), ParseTrustProxyError>(())
+    /// ```
+    pub fn express_trust_proxy(mut self, trust_proxy: &str) -> Result<Self, ParseTrustProxyError> {
+        self.config.headers = Cow::Borrowed(&[ForwardingHeader::XForwardedFor]);
+        match trust_proxy.parse()? {
+            TrustProxy::All => self.config.strategy = Strategy::LeftmostHeaderValue,
+            TrustProxy::Hops(hops) => self.config.strategy = Strategy::TrustedHopCount(hops),
+            TrustProxy::Networks(networks) => self.config.trusted_proxies.extend(networks),
+        }
+        Ok(self)
+    }
+
+    /// Resolve addresses the same way as the nginx `realip` module, configured with the `set_real_ip_from`,
+    /// `real_ip_header` and `real_ip_recursive` directives.
+    ///
+    /// The header is only used when the remote address is in `set_real_ip_from`.
+    /// Without `real_ip_recursive` the right-most address of the header is used, even if it is a trusted proxy itself.
+    /// With `real_ip_recursive` the right-most address that isn't trusted is used, or the left-most address
+    /// if every hop is trusted.
+    ///
+    /// Like nginx, empty elements are skipped and the header is only read up to the first element that isn't an address,
+    /// so a request with an invalid right-most element resolves to the remote address.
+    ///
+    /// nginx only supports `x-real-ip` and `x-forwarded-for` as the real ip header, an `x-real-ip` header
+    /// always contains a single address.
+    /// In non-recursive mode the trusted proxies are checked by a custom [`TrustPolicy`],
+    /// so further calls to [`trusted_proxy`](Self::trusted_proxy) have no effect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, IpNet, RealIpConfig};
+    /// #
+    /// // set_real_ip_from 10.0.0.0/8;
+    /// // real_ip_header X-Forwarded-For;
+    /// // real_ip_recursive on;
+    /// let set_real_ip_from = [IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8)];
+    /// let config = RealIpConfig::builder()
+    ///     .nginx_realip(set_real_ip_from, ForwardingHeader::XForwardedFor, true)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([198, 51, 100, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, unknown, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 1, 0, 3])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// // real_ip_recursive off;
+    /// let config = RealIpConfig::builder()
+    ///     .nginx_realip(set_real_ip_from, ForwardingHeader::XForwardedFor, false)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 1, 0, 3])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, not-an-ip").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn nginx_realip(
+        mut self,
+        set_real_ip_from: impl IntoIterator<Item = IpNet>,
+        real_ip_header: ForwardingHeader,
+        real_ip_recursive: bool,
+    ) -> Self {
+        let networks: Vec<IpNet> = set_real_ip_from.into_iter().collect();
+        self.config.headers = Cow::Owned(vec![real_ip_header]);
+        self.config.strategy = Strategy::RightmostUntrusted;
+        self.config.fallback = Fallback::FirstHop;
+        self.config.on_invalid = OnInvalid::UseRemote;
+        self.config.empty_elements = EmptyElements::Skip;
+        self.config.malformed_elements = MalformedElements::Terminate;
+        self.config.unknown_nodes = UnknownNodes::Terminate;
+        if !real_ip_recursive {
+            // only the remote address is checked, the right-most header value is used as is
+            let trusted = networks.clone();
+            self = self.trust_policy(move |ip, index| index == 0 && is_trusted_proxy(ip, &trusted));
+        }
+        self.config.trusted_proxies.extend(networks);
+        self
+    }
+
+    /// Resolve addresses the same way as the Apache `mod_remoteip` module, configured with the `RemoteIPHeader`,
+    /// `RemoteIPTrustedProxy` and `RemoteIPInternalProxy` directives.
+    ///
+    /// The chain is walked from the right for as long as the hops are trusted or internal proxies, using the left-most
+    /// address if every hop is trusted.
+    /// Trusted proxies that aren't internal can't report clients with an internal address,
+    /// see [`restrict_internal_clients`](Self::restrict_internal_clients).
+    /// Like Apache, every hop is trusted as a non-internal proxy when neither list contains any network,
+    /// IPv4-mapped addresses are converted to IPv4 and the header is only read up to the first element that isn't
+    /// an address.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, IpNet, RealIpConfig};
+    /// #
+    /// // RemoteIPHeader X-Forwarded-For
+    /// // RemoteIPTrustedProxy 203.0.113.0/24
+    /// // RemoteIPInternalProxy 10.0.0.0/8
+    /// let config = RealIpConfig::builder()
+    ///     .apache_remoteip(
+    ///         ForwardingHeader::XForwardedFor,
+    ///         [IpNet::new_assert(IpAddr::from([203, 0, 113, 0]), 24)],
+    ///         [IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8)],
+    ///     )
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// // the trusted proxy isn't allowed to report an internal client
+    /// let request = Request::builder().header("x-forwarded-for", "192.168.1.20, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.168.1.20, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 168, 1, 20])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn apache_remoteip(
+        mut self,
+        remote_ip_header: ForwardingHeader,
+        trusted_proxies: impl IntoIterator<Item = IpNet>,
+        internal_proxies: impl IntoIterator<Item = IpNet>,
+    ) -> Self {
+        self.config.headers = Cow::Owned(vec![remote_ip_header]);
+        self.config.strategy = Strategy::RightmostUntrusted;
+        self.config.fallback = Fallback::FirstHop;
+        self.config.on_invalid = OnInvalid::UseRemote;
+        self.config.empty_elements = EmptyElements::Terminate;
+        self.config.malformed_elements = MalformedElements::Terminate;
+        self.config.unknown_nodes = UnknownNodes::Terminate;
+        self.config.canonicalize_ipv4_mapped = true;
+        self.config.restrict_internal_clients = true;
+        self.config.trusted_proxies.extend(trusted_proxies);
+        self.config.internal_proxies.extend(internal_proxies);
+        if self.config.trusted_proxies.is_empty() && self.config.internal_proxies.is_empty() {
+            self = self.trust_policy(|_, _| true);
+        }
+        self
+    }
+
+    /// Use a custom [`TrustPolicy`] to decide which hops are trusted, instead of the list of trusted proxies
+    pub fn trust_policy(mut self, policy: impl TrustPolicy + Send + Sync + 'static) -> Self {
+        self.config.trust_policy = Some(SharedTrustPolicy(Arc::new(policy)));
+        self
+    }
+
+    /// Set the headers to check, in order of preference.
+    ///
+    /// Only the first header that is present in the request is used, headers that aren't in the list are never read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// // our CDN only sets the x-real-ip header
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers(["x-real-ip".parse::<ForwardingHeader>().unwrap()])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .header("x-real-ip", "192.0.2.1")
+    ///     .body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn headers(mut self, headers: impl IntoIterator<Item = ForwardingHeader>) -> Self {
+        self.config.headers = Cow::Owned(headers.into_iter().collect());
+        self
+    }
+
+    /// Never read the provided header, without changing the order of the other headers
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .ignore_x_real_ip()
+    ///     .build();
+    /// assert_eq!(&[ForwardingHeader::Forwarded, ForwardingHeader::XForwardedFor], config.headers());
+    ///
+    /// let request = Request::builder().header("x-real-ip", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn ignore_header(mut self, header: &ForwardingHeader) -> Self {
+        self.config
+            .headers
+            .to_mut()
+            .retain(|enabled| enabled != header);
+        self
+    }
+
+    /// Never read the `forwarded` header
+    pub fn ignore_forwarded(self) -> Self {
+        self.ignore_header(&ForwardingHeader::Forwarded)
+    }
+
+    /// Never read the `x-forwarded-for` header
+    pub fn ignore_x_forwarded_for(self) -> Self {
+        self.ignore_header(&ForwardingHeader::XForwardedFor)
+    }
+
+    /// Never read the `x-real-ip` header
+    pub fn ignore_x_real_ip(self) -> Self {
+        self.ignore_header(&ForwardingHeader::XRealIp)
+    }
+
+    /// Set what to return when all hops are trusted
+    pub fn fallback(mut self, fallback: Fallback) -> Self {
+        self.config.fallback = fallback;
+        self
+    }
+
+    /// Set the strategy for selecting the client address from the forwarding chain
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, Strategy};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .strategy(Strategy::LeftmostHeaderValue)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// Require all configured forwarding headers that are present to describe the same chain.
+    ///
+    /// Conflicting headers are a strong sign of a client trying to spoof its address, so requests with
+    /// conflicting headers are rejected and [`RealIpError::ConflictingHeaders`] is reported.
+    /// Headers that only contain the client address, like `x-real-ip`, only need to be part of the chain described by
+    /// the other headers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig, RealIpError};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .validate_consistency(true)
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("forwarded", "for=192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .body(()).unwrap();
+    /// assert_eq!(
+    ///     Err(RealIpError::ConflictingHeaders {
+    ///         header: ForwardingHeader::Forwarded,
+    ///         conflicting: ForwardingHeader::XForwardedFor,
+    ///     }),
+    ///     config.try_resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// );
+    /// ```
+    pub fn validate_consistency(mut self, validate: bool) -> Self {
+        self.config.validate_consistency = validate;
+        self
+    }
+
+    /// Reject requests where any element of the forwarding header is malformed.
+    ///
+    /// By default malformed elements are skipped, which shortens the chain and can change which hop is selected.
+    /// In strict mode [`RealIpError::MalformedHeader`] is reported instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{OnInvalid, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .strict(true)
+    ///     .on_invalid(OnInvalid::UseRemote)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, not-an-ip").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.malformed_elements = if strict {
+            MalformedElements::Reject
+        } else {
+            MalformedElements::Skip
+        };
+        self
+    }
+
+    /// Set how malformed elements in the forwarding header are handled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{MalformedElements, RealIpConfig, Strategy};
+    /// #
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, not-an-ip, 198.51.100.1").body(()).unwrap();
+    /// let resolve = |malformed_elements| {
+    ///     RealIpConfig::builder()
+    ///         .strategy(Strategy::LeftmostHeaderValue)
+    ///         .malformed_elements(malformed_elements)
+    ///         .build()
+    ///         .resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// };
+    ///
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolve(MalformedElements::Skip));
+    /// assert_eq!(None, resolve(MalformedElements::Reject));
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), resolve(MalformedElements::Terminate));
+    /// ```
+    pub fn malformed_elements(mut self, malformed_elements: MalformedElements) -> Self {
+        self.config.malformed_elements = malformed_elements;
+        self
+    }
+
+    /// Set how empty elements in the forwarding header are handled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{EmptyElements, RealIpConfig, Strategy};
+    /// #
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1,, 198.51.100.1").body(()).unwrap();
+    /// let resolve = |empty_elements| {
+    ///     RealIpConfig::builder()
+    ///         .strategy(Strategy::LeftmostHeaderValue)
+    ///         .empty_elements(empty_elements)
+    ///         .build()
+    ///         .resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// };
+    ///
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolve(EmptyElements::Skip));
+    /// assert_eq!(None, resolve(EmptyElements::Reject));
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), resolve(EmptyElements::Terminate));
+    /// ```
+    pub fn empty_elements(mut self, empty_elements: EmptyElements) -> Self {
+        self.config.empty_elements = empty_elements;
+        self
+    }
+
+    /// Reject requests where the forwarding header contains more than `max_hops` addresses.
+    ///
+    /// This stops clients from injecting excessively long chains, parsing stops as soon as the limit is exceeded
+    /// and [`RealIpError::TooManyHops`] is reported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, RealIpError};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .max_hops(2)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 192.0.2.2, 192.0.2.3").body(()).unwrap();
+    /// assert_eq!(Err(RealIpError::TooManyHops), config.try_resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn max_hops(mut self, max_hops: usize) -> Self {
+        self.config.max_hops = Some(max_hops);
+        self
+    }
+
+    /// Set the maximum length in bytes of a forwarding header value.
+    ///
+    /// Depending on [`oversized`](Self::oversized), longer values are either rejected or cut down to their right-most
+    /// complete elements that fit within the maximum length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Oversized, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .max_header_length(16)
+    ///     .oversized(Oversized::Truncate)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 198.51.100.1, 203.0.113.10").body(()).unwrap();
+    /// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), decision.ip);
+    /// assert!(decision.truncated);
+    ///
+    /// // a right-most element that fills the maximum length is kept
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1,2001:db8::abcd:1").body(()).unwrap();
+    /// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some("2001:db8::abcd:1".parse().unwrap()), decision.ip);
+    /// ```
+    pub fn max_header_length(mut self, max_header_length: usize) -> Self {
+        self.config.max_header_length = Some(max_header_length);
+        self
+    }
+
+    /// Set how header values longer than the maximum length are handled
+    pub fn oversized(mut self, oversized: Oversized) -> Self {
+        self.config.oversized = oversized;
+        self
+    }
+
+    /// Set what to return when a request is rejected because of invalid forwarding headers
+    pub fn on_invalid(mut self, on_invalid: OnInvalid) -> Self {
+        self.config.on_invalid = on_invalid;
+        self
+    }
+
+    /// Add an identity our own proxies use in the `by` parameter of the `forwarded` header.
+    ///
+    /// Once any identity is configured, every `forwarded` element added by a trusted proxy is required to have a `by`
+    /// parameter matching one of the identities, otherwise [`RealIpError::UnknownProxyIdentity`] is reported.
+    /// This catches headers fabricated by clients who don't know the internal addressing of our proxies.
+    ///
+    /// The identity is either an ip address or an obfuscated identifier like `_proxy1`, any port is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, RealIpError};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .proxy_identity("_gateway")
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("forwarded", "for=192.0.2.1;by=_gateway").body(()).unwrap();
+    /// assert_eq!(Ok(IpAddr::from([192, 0, 2, 1])), config.try_resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("forwarded", "for=192.0.2.1;by=10.0.0.1").body(()).unwrap();
+    /// assert_eq!(
+    ///     Err(RealIpError::UnknownProxyIdentity(Some("10.0.0.1".into()))),
+    ///     config.try_resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// );
+    /// ```
+    pub fn proxy_identity(mut self, identity: impl Into<String>) -> Self {
+        self.config.proxy_identities.push(identity.into());
+        self
+    }
+
+    /// Only honor forwarding headers on requests accepted by the listener with the provided local address.
+    ///
+    /// This can be called multiple times to add more listeners. Requests arriving on other listeners, or where
+    /// the local address isn't known, are resolved as if they didn't contain any forwarding header.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::{IpAddr, SocketAddr};
+    /// # use real_ip::{ConnectionInfo, RealIpConfig};
+    /// #
+    /// let internal = SocketAddr::from(([10, 0, 0, 2], 8443));
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .trusted_listener(internal)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// let connection = ConnectionInfo {
+    ///     remote: IpAddr::from([10, 0, 0, 1]).into(),
+    ///     local_addr: Some(internal),
+    ///     tls: true,
+    /// };
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, connection));
+    ///
+    /// let connection = ConnectionInfo {
+    ///     local_addr: Some(SocketAddr::from(([203, 0, 113, 1], 443))),
+    ///     ..connection
+    /// };
+    /// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), config.resolve(&request, connection));
+    /// ```
+    pub fn trusted_listener(mut self, local_addr: SocketAddr) -> Self {
+        self.config.trusted_listeners.push(local_addr);
+        self
+    }
+
+    /// Only honor forwarding headers on requests arriving over tls connections.
+    ///
+    /// Requests arriving over unencrypted connections are resolved as if they didn't contain any forwarding header.
+    pub fn require_tls(mut self, require: bool) -> Self {
+        self.config.require_tls = require;
+        self
+    }
+
+    /// Convert IPv4-mapped IPv6 addresses in the chain, like `::ffff:192.0.2.1`, to IPv4 addresses.
+    ///
+    /// Trusted networks always match IPv4-mapped addresses, see [`is_trusted_proxy`](crate::is_trusted_proxy).
+    /// With this enabled the converted addresses are also passed to a custom [`TrustPolicy`] and returned as result.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{IpNet, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8))
+    ///     .canonicalize_ipv4_mapped(true)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "::ffff:192.0.2.1").body(()).unwrap();
+    /// let remote: IpAddr = "::ffff:10.0.0.1".parse().unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, remote));
+    /// ```
+    pub fn canonicalize_ipv4_mapped(mut self, canonicalize: bool) -> Self {
+        self.config.canonicalize_ipv4_mapped = canonicalize;
+        self
+    }
+
+    /// Set how the zone id of IPv6 addresses in a forwarding header is handled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, ZoneIds};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .zone_ids(ZoneIds::Preserve)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "fe80::1%eth0").body(()).unwrap();
+    /// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some("fe80::1".parse().unwrap()), decision.ip);
+    /// assert_eq!(Some("eth0"), decision.hops[0].zone.as_deref());
+    ///
+    /// let request = Request::builder().header("forwarded", "for=\"[fe80::1%25eth0]:8080\";proto=https").body(()).unwrap();
+    /// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some("fe80::1".parse().unwrap()), decision.ip);
+    /// assert_eq!(Some("https"), decision.proto.as_deref());
+    /// assert_eq!(Some("eth0"), decision.hops[0].zone.as_deref());
+    /// ```
+    pub fn zone_ids(mut self, zone_ids: ZoneIds) -> Self {
+        self.config.zone_ids = zone_ids;
+        self
+    }
+
+    /// Set how `unknown` and obfuscated nodes in the forwarding header are handled
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, RealIpError, UnknownNodes};
+    /// #
+    /// let request = Request::builder().header("forwarded", "for=192.0.2.1, for=unknown").body(()).unwrap();
+    /// let remote = IpAddr::from([10, 0, 0, 1]);
+    ///
+    /// let config = RealIpConfig::builder().trusted_proxy(remote.into()).build();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, remote));
+    ///
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(remote.into())
+    ///     .unknown_nodes(UnknownNodes::Untrusted)
+    ///     .build();
+    /// assert_eq!(None, config.resolve(&request, remote));
+    /// assert_eq!(Err(RealIpError::UnknownNode), config.try_resolve(&request, remote));
+    /// ```
+    pub fn unknown_nodes(mut self, unknown_nodes: UnknownNodes) -> Self {
+        self.config.unknown_nodes = unknown_nodes;
+        self
+    }
+
+    /// Reject requests where the resolved client address is in private, loopback, link-local, carrier-grade nat
+    /// or other reserved address space.
+    ///
+    /// Such a client address almost always indicates a misconfigured proxy. Rejected requests report
+    /// [`RealIpError::ReservedClient`] and return the address configured by [`on_invalid`](Self::on_invalid).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{OnInvalid, RealIpConfig, RealIpError};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .reject_reserved_client(true)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "93.184.215.14").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([93, 184, 215, 14])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "172.16.0.20").body(()).unwrap();
+    /// assert_eq!(
+    ///     Err(RealIpError::ReservedClient(IpAddr::from([172, 16, 0, 20]))),
+    ///     config.try_resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// );
+    /// ```
+    pub fn reject_reserved_client(mut self, reject: bool) -> Self {
+        self.config.reject_reserved_client = reject;
+        self
+    }
+
+    /// Trust every hop in private, loopback, link-local or carrier-grade nat address space, in addition to the
+    /// trusted proxies.
+    ///
+    /// The chain is walked from the right, skipping private hops until the first globally routable address is found.
+    /// This mirrors how many deployments using nginx with `real_ip_recursive on` behave, but note that it allows any
+    /// host in the private network to spoof the client address.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::RealIpConfig;
+    /// #
+    /// let config = RealIpConfig::builder().trust_private_hops(true).build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "93.184.215.14, 172.16.0.20, 10.1.0.3").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([93, 184, 215, 14])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    pub fn trust_private_hops(mut self, trust: bool) -> Self {
+        self.config.trust_private_hops = trust;
+        self
+    }
+
+    /// Add an internal proxy.
+    ///
+    /// Internal proxies are trusted, and unlike other trusted proxies they are allowed to report clients with an
+    /// internal address when [`restrict_internal_clients`](Self::restrict_internal_clients) is enabled.
+    pub fn internal_proxy(mut self, proxy: IpNet) -> Self {
+        self.config.internal_proxies.push(proxy);
+        self
+    }
+
+    /// Only accept clients with an internal address, an address in the IPv4 private networks or an IPv6 address
+    /// outside of global unicast `2000::/3`, when they are reported by an [internal proxy](Self::internal_proxy).
+    ///
+    /// When a trusted proxy that isn't internal reports an internal address the chain ends at that proxy,
+    /// and the proxy itself is used as the client.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::RealIpConfig;
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([203, 0, 113, 10]).into())
+    ///     .internal_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .restrict_internal_clients(true)
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.168.1.20").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 168, 1, 20])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), config.resolve(&request, IpAddr::from([203, 0, 113, 10])));
+    /// ```
+    pub fn restrict_internal_clients(mut self, restrict: bool) -> Self {
+        self.config.restrict_internal_clients = restrict;
+        self
+    }
+
+    /// Build the configuration
+    pub fn build(self) -> RealIpConfig {
+        self.config
+    }
+}
@@ -0,0 +1,220 @@
+use crate::headers::ViaEntry;
+use crate::{ForwardingHeader, RealIpError};
+use http::Extensions;
+use std::net::{IpAddr, SocketAddr};
+
+/// A single hop in the forwarding chain
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Hop {
+    /// The address of the hop
+    pub ip: IpAddr,
+    /// The port of the hop, if it was provided
+    pub port: Option<u16>,
+    /// The protocol used by the hop to connect to the next proxy, as reported by the `proto` parameter of the
+    /// `forwarded` header or by the `x-forwarded-proto` header.
+    ///
+    /// This is only trustworthy if the hop after this one is trusted.
+    pub proto: Option<String>,
+    /// The host requested by the hop, as reported by the `host` parameter of the `forwarded` header or by the
+    /// `x-forwarded-host` header.
+    ///
+    /// This is only trustworthy if the hop after this one is trusted.
+    pub host: Option<String>,
+    /// The port the hop connected to on the next proxy, as reported by the `x-forwarded-port` header.
+    ///
+    /// This is only trustworthy if the hop after this one is trusted.
+    pub forwarded_port: Option<u16>,
+    /// The proxy that received the request from this hop, as reported by the `by` parameter of the `forwarded`
+    /// header, without port.
+    ///
+    /// This is either an ip address, `unknown` or an obfuscated identifier.
+    pub by: Option<String>,
+    /// The zone id of the address, if it was provided and [`ZoneIds::Preserve`](crate::ZoneIds::Preserve) is configured
+    pub zone: Option<String>,
+    /// Whether the hop is a trusted proxy
+    pub trusted: bool,
+    /// The header this hop was read from, or `None` for the remote address of the request
+    pub source_header: Option<ForwardingHeader>,
+}
+
+impl Hop {
+    /// The address of the hop, including the port if it was provided
+    pub fn client_addr(&self) -> ClientAddr {
+        ClientAddr {
+            ip: self.ip,
+            port: self.port,
+        }
+    }
+}
+
+/// The address of a client, with the port if it is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientAddr {
+    /// The ip address of the client
+    pub ip: IpAddr,
+    /// The port of the client, if it is known
+    pub port: Option<u16>,
+}
+
+impl ClientAddr {
+    /// The socket address of the client, if the port is known
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        Some(SocketAddr::new(self.ip, self.port?))
+    }
+}
+
+impl From<ClientAddr> for IpAddr {
+    fn from(addr: ClientAddr) -> Self {
+        addr.ip
+    }
+}
+
+/// A report of how the "real-ip" of a request was determined
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{ForwardingHeader, RealIpConfig};
+/// #
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+/// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), decision.ip);
+/// assert_eq!(None, decision.proto);
+/// assert_eq!(None, decision.host);
+/// assert_eq!(Some(ForwardingHeader::XForwardedFor), decision.header);
+/// assert_eq!(
+///     vec![false, false, true],
+///     decision.hops.iter().map(|hop| hop.trusted).collect::<Vec<_>>()
+/// );
+///
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-proto", "https")
+///     .body(()).unwrap();
+/// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), decision.ip);
+/// assert_eq!(Some("https"), decision.proto.as_deref());
+///
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-port", "8443")
+///     .body(()).unwrap();
+/// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(Some(8443), decision.forwarded_port);
+/// // a client connecting directly can't report the port it connected to
+/// let decision = config.resolve_with_report(&request, IpAddr::from([203, 0, 113, 10]));
+/// assert_eq!(None, decision.forwarded_port);
+///
+/// let request = Request::builder()
+///     .header("forwarded", "for=192.0.2.1;host=Example.com;proto=https")
+///     .body(()).unwrap();
+/// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(Some("example.com"), decision.host.as_deref());
+///
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("via", "1.1 edge.example.com, 1.1 lb.internal")
+///     .body(()).unwrap();
+/// let decision = config.resolve_with_report(&request, IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(
+///     vec!["edge.example.com", "lb.internal"],
+///     decision.via.iter().map(|entry| entry.received_by.as_str()).collect::<Vec<_>>()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Decision {
+    /// The resolved address, the same as would be returned by `resolve`
+    pub ip: Option<IpAddr>,
+    /// The protocol the client used to connect to the first trusted proxy, e.g. `https`
+    ///
+    /// This is always `None` when the client connected directly.
+    pub proto: Option<String>,
+    /// The host requested by the client from the first trusted proxy, including the port if one was requested
+    ///
+    /// This is always `None` when the client connected directly, use the `host` header of the request instead.
+    pub host: Option<String>,
+    /// The port the client connected to on the first trusted proxy, as reported by `x-forwarded-port`
+    ///
+    /// This is always `None` when the client connected directly.
+    pub forwarded_port: Option<u16>,
+    /// The header the forwarding chain was taken from
+    pub header: Option<ForwardingHeader>,
+    /// All hops in the chain, from the original client to the remote address of the request
+    pub hops: Vec<Hop>,
+    /// The reason the request was rejected, if it was
+    pub error: Option<RealIpError>,
+    /// Whether an oversized header value was truncated, see [`Oversized::Truncate`](crate::Oversized::Truncate)
+    pub truncated: bool,
+    /// The proxies listed in the `via` headers of the request, in the order the request passed through them
+    ///
+    /// These are not used to resolve the address, but can be compared against the trusted hops.
+    pub via: Vec<ViaEntry>,
+}
+
+impl Decision {
+    /// The decision cached in the extensions of a request by
+    /// [`RealIpConfig::resolve_cached`](crate::RealIpConfig::resolve_cached) or
+    /// [`Resolver::resolve_cached`](crate::Resolver::resolve_cached), if any
+    pub fn cached(extensions: &Extensions) -> Option<&Decision> {
+        extensions.get()
+    }
+}
+
+/// Everything the trusted proxies report about the original request, taken from a single parse of the forwarding headers
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{forwarded_context, IpNet};
+/// #
+/// let trusted_proxies = [IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8)];
+/// let request = Request::builder()
+///     .header("forwarded", "for=\"192.0.2.1:46532\";proto=https;host=example.com, for=10.10.10.10")
+///     .body(()).unwrap();
+///
+/// let context = forwarded_context(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies).unwrap();
+/// assert_eq!(IpAddr::from([192, 0, 2, 1]), context.ip);
+/// assert_eq!(Some(46532), context.port);
+/// assert_eq!(Some("https"), context.proto.as_deref());
+/// assert_eq!(Some("example.com"), context.host.as_deref());
+/// assert_eq!(
+///     vec![IpAddr::from([10, 10, 10, 10]), IpAddr::from([10, 0, 0, 1])],
+///     context.proxies.iter().map(|hop| hop.ip).collect::<Vec<_>>()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ForwardedContext {
+    /// The address of the client
+    pub ip: IpAddr,
+    /// The port of the client, if it was provided
+    pub port: Option<u16>,
+    /// The protocol the client used to connect to the first trusted proxy, e.g. `https`
+    pub proto: Option<String>,
+    /// The host requested by the client from the first trusted proxy
+    pub host: Option<String>,
+    /// The port the client connected to on the first trusted proxy, as reported by `x-forwarded-port`
+    pub forwarded_port: Option<u16>,
+    /// The proxies the request passed through after leaving the client, ending with the remote address of the request
+    pub proxies: Vec<Hop>,
+}
+
+impl ForwardedContext {
+    /// The address of the client, including the port if it was provided
+    pub fn client_addr(&self) -> ClientAddr {
+        ClientAddr {
+            ip: self.ip,
+            port: self.port,
+        }
+    }
+}
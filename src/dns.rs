@@ -0,0 +1,167 @@
+use crate::shared::SourceId;
+use crate::SharedResolver;
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::lookup_ip::LookupIp;
+use hickory_resolver::TokioAsyncResolver;
+use ipnet::IpNet;
+use std::io;
+use std::net::IpAddr;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// How long to wait before looking up a hostname again after the lookup failed
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The minimum time between lookups of a hostname, even if its records have a shorter ttl
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Refreshes the addresses of trusted proxy hostnames for a [`SharedResolver`], see [`SharedResolver::trust_hostnames`].
+///
+/// The hostnames are refreshed until this is dropped, after which their addresses are no longer trusted.
+///
+/// Requires the `dns` feature.
+#[derive(Debug)]
+pub struct ProxyHostnameRefresher {
+    task: JoinHandle<()>,
+    resolver: Weak<SharedResolver>,
+    source: SourceId,
+}
+
+impl Drop for ProxyHostnameRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
+        if let Some(resolver) = self.resolver.upgrade() {
+            resolver.remove_source(self.source);
+        }
+    }
+}
+
+/// The cached addresses of a trusted proxy hostname
+struct Hostname {
+    name: String,
+    addresses: Vec<IpAddr>,
+    valid_until: Instant,
+}
+
+impl Hostname {
+    async fn resolve(name: String, dns: &TokioAsyncResolver) -> Result<Self, ResolveError> {
+        let lookup = dns.lookup_ip(name.as_str()).await?;
+        let mut hostname = Hostname {
+            name,
+            addresses: Vec::new(),
+            valid_until: Instant::now(),
+        };
+        hostname.update(&lookup);
+        Ok(hostname)
+    }
+
+    /// Look up the hostname again, keeping the cached addresses if the lookup fails
+    async fn refresh(&mut self, dns: &TokioAsyncResolver) {
+        match dns.lookup_ip(self.name.as_str()).await {
+            Ok(lookup) => self.update(&lookup),
+            Err(err) => match err.kind() {
+                ResolveErrorKind::NoRecordsFound { negative_ttl, .. } => {
+                    self.addresses.clear();
+                    let ttl = negative_ttl.map_or(RETRY_INTERVAL, |ttl| {
+                        Duration::from_secs(ttl.into()).max(MIN_REFRESH_INTERVAL)
+                    });
+                    self.valid_until = Instant::now() + ttl;
+                }
+                _ => self.valid_until = Instant::now() + RETRY_INTERVAL,
+            },
+        }
+    }
+
+    fn update(&mut self, lookup: &LookupIp) {
+        self.addresses = lookup.iter().collect();
+        self.valid_until = lookup
+            .valid_until()
+            .max(Instant::now() + MIN_REFRESH_INTERVAL);
+    }
+}
+
+impl SharedResolver {
+    /// Trust the addresses of proxy hostnames, in addition to the configured trusted proxies.
+    ///
+    /// The hostnames are resolved with the DNS configuration of the system,
+    /// see [`trust_hostnames_with`](Self::trust_hostnames_with) for details.
+    ///
+    /// Requires the `dns` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::net::IpAddr;
+    /// # use std::sync::Arc;
+    /// # use real_ip::SharedResolver;
+    /// #
+    /// # async fn example() -> std::io::Result<()> {
+    /// let resolver = Arc::new(SharedResolver::new([IpAddr::from([10, 0, 0, 1]).into()]));
+    /// let _refresher = resolver.trust_hostnames(["proxy.internal.example"]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn trust_hostnames(
+        self: &Arc<Self>,
+        hostnames: impl IntoIterator<Item = impl Into<String>>,
+    ) -> io::Result<ProxyHostnameRefresher> {
+        let dns = TokioAsyncResolver::tokio_from_system_conf()?;
+        self.trust_hostnames_with(dns, hostnames).await
+    }
+
+    /// Trust the addresses of proxy hostnames resolved with the provided DNS resolver,
+    /// in addition to the configured trusted proxies.
+    ///
+    /// Resolving the hostnames when starting has to succeed. Afterwards every hostname is resolved again when its records
+    /// expire, if that lookup fails the previous addresses are kept and the lookup is retried later.
+    /// A hostname without any records is no longer trusted.
+    ///
+    /// The hostnames are refreshed by a task on the current tokio runtime.
+    /// Every refresh only replaces the addresses of these hostnames, the configured trusted proxies and the networks of
+    /// other sources like `refresh_ranges` stay trusted.
+    ///
+    /// Requires the `dns` feature.
+    pub async fn trust_hostnames_with(
+        self: &Arc<Self>,
+        dns: TokioAsyncResolver,
+        hostnames: impl IntoIterator<Item = impl Into<String>>,
+    ) -> io::Result<ProxyHostnameRefresher> {
+        let mut resolved = Vec::new();
+        for name in hostnames {
+            resolved.push(Hostname::resolve(name.into(), &dns).await?);
+        }
+        let source = self.add_source();
+        self.update_source(source, trusted_proxies(&resolved));
+
+        let resolver = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            loop {
+                let next = match resolved.iter().map(|hostname| hostname.valid_until).min() {
+                    Some(next) => next,
+                    None => return,
+                };
+                tokio::time::sleep_until(next.into()).await;
+
+                let now = Instant::now();
+                for hostname in resolved.iter_mut() {
+                    if hostname.valid_until <= now {
+                        hostname.refresh(&dns).await;
+                    }
+                }
+                resolver.update_source(source, trusted_proxies(&resolved));
+            }
+        });
+        Ok(ProxyHostnameRefresher {
+            task,
+            resolver: Arc::downgrade(self),
+            source,
+        })
+    }
+}
+
+fn trusted_proxies(resolved: &[Hostname]) -> impl Iterator<Item = IpNet> + '_ {
+    resolved
+        .iter()
+        .flat_map(|hostname| hostname.addresses.iter().copied().map(IpNet::from))
+}
@@ -0,0 +1,85 @@
+use crate::ForwardingHeader;
+use http::HeaderValue;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+
+/// The reason the "real-ip" of a request couldn't be determined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RealIpError {
+    /// The request was made by a trusted proxy, but it didn't contain any forwarding header
+    NoForwardingHeader,
+    /// The forwarding header didn't contain any valid address
+    MalformedHeader {
+        header: ForwardingHeader,
+        value: HeaderValue,
+    },
+    /// An untrusted hop claims to have forwarded the request for another client
+    ///
+    /// The contained address is the untrusted hop, which is what [`real_ip`](crate::real_ip) would return
+    UntrustedProxyInChain(IpAddr),
+    /// Every hop in the chain is a trusted proxy, so no client address can be determined
+    AllHopsTrusted,
+    /// The forwarding chain contains more hops than the configured maximum
+    TooManyHops,
+    /// The forwarding header is longer than the configured maximum
+    HeaderTooLarge { header: ForwardingHeader },
+    /// Multiple forwarding headers are present that describe a different chain
+    ConflictingHeaders {
+        header: ForwardingHeader,
+        conflicting: ForwardingHeader,
+    },
+    /// A trusted proxy added a `forwarded` element without one of the configured proxy identities as `by` parameter
+    ///
+    /// The contained value is the `by` parameter of the element, if it had one
+    UnknownProxyIdentity(Option<String>),
+    /// The forwarding chain contains an `unknown` or obfuscated node where the client address is expected,
+    /// see [`UnknownNodes`](crate::UnknownNodes)
+    UnknownNode,
+    /// The resolved client address is in private or otherwise reserved address space,
+    /// see [`RealIpConfigBuilder::reject_reserved_client`](crate::RealIpConfigBuilder::reject_reserved_client)
+    ReservedClient(IpAddr),
+}
+
+impl Display for RealIpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RealIpError::NoForwardingHeader => {
+                write!(
+                    f,
+                    "request from trusted proxy contains no forwarding header"
+                )
+            }
+            RealIpError::MalformedHeader { header, value } => {
+                write!(f, "malformed {} header: {:?}", header.name(), value)
+            }
+            RealIpError::UntrustedProxyInChain(ip) => {
+                write!(f, "untrusted proxy {} in forwarding chain", ip)
+            }
+            RealIpError::AllHopsTrusted => {
+                write!(f, "all hops in the forwarding chain are trusted")
+            }
+            RealIpError::TooManyHops => write!(f, "forwarding chain contains too many hops"),
+            RealIpError::HeaderTooLarge { header } => write!(f, "{} header is too large", header),
+            RealIpError::ConflictingHeaders {
+                header,
+                conflicting,
+            } => write!(f, "{} header conflicts with {} header", header, conflicting),
+            RealIpError::UnknownProxyIdentity(Some(by)) => {
+                write!(f, "unknown proxy identity {} in forwarded header", by)
+            }
+            RealIpError::ReservedClient(ip) => {
+                write!(f, "client address {} is in reserved address space", ip)
+            }
+            RealIpError::UnknownNode => write!(f, "forwarding chain contains an unknown node"),
+            RealIpError::UnknownProxyIdentity(None) => {
+                write!(
+                    f,
+                    "forwarded element from trusted proxy has no proxy identity"
+                )
+            }
+        }
+    }
+}
+
+impl Error for RealIpError {}
@@ -0,0 +1,82 @@
+use crate::reserved::{LINK_LOCAL, LOOPBACK, PRIVATE};
+use ipnet::{ip_mask_to_prefix, IpNet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A parsed Express.js `trust proxy` setting
+pub(crate) enum TrustProxy {
+    /// Trust every hop, using the left-most address
+    All,
+    /// Trust a fixed number of hops from the right
+    Hops(usize),
+    /// Trust the listed networks
+    Networks(Vec<IpNet>),
+}
+
+impl FromStr for TrustProxy {
+    type Err = ParseTrustProxyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "true" => return Ok(TrustProxy::All),
+            "false" => return Ok(TrustProxy::Hops(0)),
+            s => {
+                if let Ok(hops) = s.parse() {
+                    return Ok(TrustProxy::Hops(hops));
+                }
+            }
+        }
+
+        let mut networks = Vec::new();
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            match entry {
+                "loopback" => networks.extend(LOOPBACK.to_ipnets()),
+                "linklocal" => networks.extend(LINK_LOCAL.to_ipnets()),
+                "uniquelocal" => networks.extend(PRIVATE.to_ipnets()),
+                _ => networks.push(parse_network(entry)?),
+            }
+        }
+        Ok(TrustProxy::Networks(networks))
+    }
+}
+
+/// Parse an address, a network in cidr notation or a network with netmask like `10.0.0.0/255.0.0.0`
+fn parse_network(entry: &str) -> Result<IpNet, ParseTrustProxyError> {
+    let invalid = || ParseTrustProxyError(entry.into());
+    match entry.split_once('/') {
+        None => IpAddr::from_str(entry)
+            .map(IpNet::from)
+            .map_err(|_| invalid()),
+        Some((addr, mask)) => {
+            let addr = IpAddr::from_str(addr).map_err(|_| invalid())?;
+            let prefix = match IpAddr::from_str(mask) {
+                Ok(mask) if mask.is_ipv4() == addr.is_ipv4() => {
+                    ip_mask_to_prefix(mask).map_err(|_| invalid())?
+                }
+                Ok(_) => return Err(invalid()),
+                Err(_) => mask.parse().map_err(|_| invalid())?,
+            };
+            IpNet::new(addr, prefix).map_err(|_| invalid())
+        }
+    }
+}
+
+/// Error returned when parsing an invalid Express.js `trust proxy` setting
+///
+/// The contained value is the part of the setting that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTrustProxyError(pub String);
+
+impl Display for ParseTrustProxyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid trust proxy setting: {}", self.0)
+    }
+}
+
+impl Error for ParseTrustProxyError {}
@@ -0,0 +1,287 @@
+use crate::layer::{remote_from_extensions, LayerContext};
+use crate::{
+    ClientIp, ClientIpRejection, ConnectionInfo, Decision, ForwardingHeader, RealIpConfig,
+    RealIpLayer, Resolver, DEFAULT_HEADERS,
+};
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use http::request::Parts;
+use std::net::{IpAddr, SocketAddr};
+
+/// Extract the "real-ip" of the request in an axum handler
+///
+/// The address resolved by the [`RealIpLayer`](crate::RealIpLayer) is used when the layer is applied.
+/// Otherwise the address is resolved from the `ConnectInfo<SocketAddr>` of the request, with a [`Resolver`] or
+/// [`RealIpConfig`] added to the request extensions with `axum::Extension`.
+///
+/// # Example
+///
+/// ```rust
+/// # use axum::{routing::get, Router};
+/// # use std::net::IpAddr;
+/// # use real_ip::{ClientIp, RealIpConfig, RealIpLayer};
+/// #
+/// async fn handler(ClientIp(ip): ClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .layer(RealIpLayer::new(config));
+/// ```
+///
+/// Without the layer:
+///
+/// ```rust
+/// # use axum::{body::Body, extract::ConnectInfo, http::{Request, StatusCode}, routing::get, Extension, Router};
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use tower::ServiceExt;
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// # async fn handler(ClientIp(ip): ClientIp) -> String {
+/// #     ip.to_string()
+/// # }
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .layer(Extension(config));
+///
+/// let mut request = Request::builder().uri("/").header("x-forwarded-for", "192.0.2.1").body(Body::empty()).unwrap();
+/// request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 46532))));
+/// assert_eq!(StatusCode::OK, app.clone().oneshot(request).await.unwrap().status());
+///
+/// // the server wasn't started with `into_make_service_with_connect_info`
+/// let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+/// assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, app.oneshot(request).await.unwrap().status());
+/// # }
+/// ```
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = ClientIpRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(ip) = parts.extensions.get::<ClientIp>() {
+            return Ok(*ip);
+        }
+        if let Some(decision) = Decision::cached(&parts.extensions) {
+            // the layer already resolved the request, without finding an address
+            return Err(ClientIpRejection::Unresolved(decision.error.clone()));
+        }
+        let remote = match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => *addr,
+            None => return Err(ClientIpRejection::MissingConnectInfo),
+        };
+        let decision = if let Some(resolver) = parts.extensions.get::<Resolver>().cloned() {
+            resolver.resolve_cached(parts, remote)
+        } else if let Some(config) = parts.extensions.get::<RealIpConfig>().cloned() {
+            config.resolve_cached(parts, remote)
+        } else {
+            return Err(ClientIpRejection::MissingConfig);
+        };
+        match decision.ip {
+            Some(ip) => {
+                parts.extensions.insert(ClientIp(ip));
+                Ok(ClientIp(ip))
+            }
+            None => Err(ClientIpRejection::Unresolved(decision.error.clone())),
+        }
+    }
+}
+
+/// Extract the "real-ip" of the request in an axum handler, rejecting requests with a forwarding chain that can't be
+/// fully verified
+///
+/// Unlike [`ClientIp`] this fails in the same cases as [`try_real_ip`](crate::try_real_ip), for example when an
+/// untrusted hop claims to forward the request for another client.
+/// The configuration is found the same way as for [`ClientIp`].
+///
+/// # Example
+///
+/// ```rust
+/// # use axum::{body::Body, http::{Request, StatusCode}, routing::get, Router};
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use tower::ServiceExt;
+/// # use real_ip::{RealIpConfig, RealIpLayer, SecureClientIp};
+/// #
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// async fn handler(SecureClientIp(ip): SecureClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .layer(RealIpLayer::new(config));
+///
+/// let mut request = Request::builder().uri("/").header("x-forwarded-for", "192.0.2.1").body(Body::empty()).unwrap();
+/// request.extensions_mut().insert(SocketAddr::from(([10, 0, 0, 1], 46532)));
+/// assert_eq!(StatusCode::OK, app.clone().oneshot(request).await.unwrap().status());
+///
+/// // 203.0.113.10 isn't trusted to forward requests
+/// let mut request = Request::builder()
+///     .uri("/")
+///     .header("x-forwarded-for", "192.0.2.1, 203.0.113.10")
+///     .body(Body::empty())
+///     .unwrap();
+/// request.extensions_mut().insert(SocketAddr::from(([10, 0, 0, 1], 46532)));
+/// assert_eq!(StatusCode::BAD_REQUEST, app.oneshot(request).await.unwrap().status());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SecureClientIp(pub IpAddr);
+
+impl From<SecureClientIp> for IpAddr {
+    fn from(ip: SecureClientIp) -> Self {
+        ip.0
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SecureClientIp {
+    type Rejection = ClientIpRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let result = if let Some(context) = parts.extensions.get::<LayerContext>() {
+            context.resolver.try_resolve(&*parts, context.connection)
+        } else {
+            let remote = match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+                Some(ConnectInfo(addr)) => *addr,
+                None => return Err(ClientIpRejection::MissingConnectInfo),
+            };
+            if let Some(resolver) = parts.extensions.get::<Resolver>() {
+                resolver.try_resolve(&*parts, remote)
+            } else if let Some(config) = parts.extensions.get::<RealIpConfig>() {
+                config.try_resolve(&*parts, remote)
+            } else {
+                return Err(ClientIpRejection::MissingConfig);
+            }
+        };
+        result
+            .map(SecureClientIp)
+            .map_err(|error| ClientIpRejection::Unresolved(Some(error)))
+    }
+}
+
+/// Extract the left-most address of the forwarding headers without any validation, for analytics and other uses
+/// where a spoofed address is harmless
+///
+/// Every hop in the forwarding headers can be set by the client, so this should never be used for access control
+/// or rate limiting, use [`ClientIp`] or [`SecureClientIp`] instead.
+/// The configured headers are used when a configuration is found the same way as for [`ClientIp`],
+/// otherwise the [`DEFAULT_HEADERS`]. Requests without forwarding header use the remote address of the connection.
+///
+/// # Example
+///
+/// ```rust
+/// # use axum::{body::{to_bytes, Body}, http::Request, routing::get, Router};
+/// # use std::net::SocketAddr;
+/// # use tower::ServiceExt;
+/// # use real_ip::InsecureClientIp;
+/// #
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// async fn handler(InsecureClientIp(ip): InsecureClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// let app: Router = Router::new().route("/", get(handler));
+///
+/// let mut request = Request::builder()
+///     .uri("/")
+///     .header("x-forwarded-for", "192.0.2.1, 203.0.113.10")
+///     .body(Body::empty())
+///     .unwrap();
+/// request.extensions_mut().insert(SocketAddr::from(([10, 0, 0, 1], 46532)));
+/// let body = app.oneshot(request).await.unwrap().into_body();
+/// assert_eq!("192.0.2.1", to_bytes(body, 64).await.unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InsecureClientIp(pub IpAddr);
+
+impl From<InsecureClientIp> for IpAddr {
+    fn from(ip: InsecureClientIp) -> Self {
+        ip.0
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for InsecureClientIp {
+    type Rejection = ClientIpRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let context = parts.extensions.get::<LayerContext>();
+        let headers: &[ForwardingHeader] = if let Some(context) = context {
+            context.resolver.config().headers()
+        } else if let Some(resolver) = parts.extensions.get::<Resolver>() {
+            resolver.config().headers()
+        } else if let Some(config) = parts.extensions.get::<RealIpConfig>() {
+            config.headers()
+        } else {
+            &DEFAULT_HEADERS
+        };
+        let forwarded = headers
+            .iter()
+            .find_map(|header| header.extract_all(&parts.headers).next());
+        let remote = context
+            .map(|context| context.connection)
+            .or_else(|| remote_from_extensions(&parts.extensions))
+            .and_then(|connection: ConnectionInfo| connection.remote.ip());
+        forwarded
+            .or(remote)
+            .map(InsecureClientIp)
+            .ok_or(ClientIpRejection::MissingConnectInfo)
+    }
+}
+
+/// Apply the [`RealIpLayer`](crate::RealIpLayer) to a router and turn it into a service that records the remote
+/// address of every connection, for use with `axum::serve`
+///
+/// This replaces `into_make_service`, without the remote address of the connection the extractors can't verify
+/// the forwarding headers.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use axum::{routing::get, Router};
+/// # use std::net::IpAddr;
+/// # use real_ip::{real_ip_make_service, ClientIp, RealIpConfig};
+/// #
+/// async fn handler(ClientIp(ip): ClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app = Router::new().route("/", get(handler));
+///
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
+/// axum::serve(listener, real_ip_make_service(app, config)).await.unwrap();
+/// # }
+/// ```
+pub fn real_ip_make_service(
+    router: Router,
+    resolver: impl Into<Resolver>,
+) -> IntoMakeServiceWithConnectInfo<Router, SocketAddr> {
+    router
+        .layer(RealIpLayer::new(resolver))
+        .into_make_service_with_connect_info::<SocketAddr>()
+}
+
+impl IntoResponse for ClientIpRejection {
+    fn into_response(self) -> Response {
+        (self.status(), self.to_string()).into_response()
+    }
+}
@@ -0,0 +1,294 @@
+use crate::shared::SourceId;
+use crate::{AwsIpRanges, SharedResolver, TrustedProxies};
+use ipnet::IpNet;
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use std::io;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{interval_at, Instant};
+
+/// How long a single download of a feed can take
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+const CLOUDFLARE_V4: &str = "https://www.cloudflare.com/ips-v4";
+const CLOUDFLARE_V6: &str = "https://www.cloudflare.com/ips-v6";
+const FASTLY: &str = "https://api.fastly.com/public-ip-list";
+const AWS: &str = "https://ip-ranges.amazonaws.com/ip-ranges.json";
+const GOOGLE: &str = "https://www.gstatic.com/ipranges/goog.json";
+
+/// A published list of proxy ranges that can be downloaded, see [`SharedResolver::refresh_ranges`].
+///
+/// Requires the `fetch` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RangeFeed {
+    /// The ranges of Cloudflare's edge servers, from <https://www.cloudflare.com/ips-v4> and <https://www.cloudflare.com/ips-v6>
+    Cloudflare,
+    /// The ranges of Fastly's edge servers, from <https://api.fastly.com/public-ip-list>
+    Fastly,
+    /// The ranges of an AWS service like `CLOUDFRONT_ORIGIN_FACING`, optionally limited to a single region,
+    /// from <https://ip-ranges.amazonaws.com/ip-ranges.json>
+    ///
+    /// See [`AwsIpRanges::service`] for details.
+    Aws {
+        service: String,
+        region: Option<String>,
+    },
+    /// All ranges used by Google, from <https://www.gstatic.com/ipranges/goog.json>
+    ///
+    /// This includes the ranges of Google Cloud load balancers, but also the ranges of Google Cloud customers,
+    /// prefer `TrustedProxies::google_cloud_load_balancer` when only trusting load balancers.
+    Google,
+    /// The ranges of an Azure service tag like `AzureFrontDoor.Backend`, from a service tag file
+    ///
+    /// Microsoft publishes the service tags in a new file every week, without a stable url.
+    /// The url has to point to a copy of the file downloaded from
+    /// <https://www.microsoft.com/en-us/download/details.aspx?id=56519> or the service tag discovery api.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use real_ip::RangeFeed;
+    /// #
+    /// let feed = RangeFeed::AzureServiceTag {
+    ///     url: "https://config.internal.example/ServiceTags_Public.json".into(),
+    ///     tag: "AzureFrontDoor.Backend".into(),
+    /// };
+    /// ```
+    AzureServiceTag { url: String, tag: String },
+}
+
+impl RangeFeed {
+    /// Download the current ranges of the feed
+    pub async fn fetch(&self, client: &Client) -> io::Result<TrustedProxies> {
+        Ok(self.fetch_versioned(client).await?.ranges)
+    }
+
+    async fn fetch_versioned(&self, client: &Client) -> io::Result<Fetched> {
+        match self {
+            RangeFeed::Cloudflare => {
+                let v4 = parse_list(&get(client, CLOUDFLARE_V4).await?)?;
+                let v6 = parse_list(&get(client, CLOUDFLARE_V6).await?)?;
+                Ok(Fetched::new(v4.with(v6)))
+            }
+            RangeFeed::Fastly => {
+                let list: FastlyList = parse_json(&get(client, FASTLY).await?)?;
+                Ok(Fetched::new(
+                    list.addresses
+                        .into_iter()
+                        .chain(list.ipv6_addresses)
+                        .collect(),
+                ))
+            }
+            RangeFeed::Aws { service, region } => {
+                let ranges = AwsIpRanges::parse(&get(client, AWS).await?)?;
+                Ok(Fetched {
+                    ranges: ranges.service(service, region.as_deref()),
+                    version: ranges.sync_token().parse().ok(),
+                })
+            }
+            RangeFeed::Google => {
+                let list: GoogleList = parse_json(&get(client, GOOGLE).await?)?;
+                Ok(Fetched {
+                    ranges: list
+                        .prefixes
+                        .into_iter()
+                        .filter_map(|prefix| prefix.ipv4_prefix.or(prefix.ipv6_prefix))
+                        .collect(),
+                    version: list.sync_token.parse().ok(),
+                })
+            }
+            RangeFeed::AzureServiceTag { url, tag } => {
+                let tags: AzureServiceTags = parse_json(&get(client, url).await?)?;
+                let tag = tags
+                    .values
+                    .into_iter()
+                    .find(|value| value.name.eq_ignore_ascii_case(tag))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("service tag {} not found", tag),
+                        )
+                    })?;
+                Ok(Fetched {
+                    ranges: tag.properties.address_prefixes.into_iter().collect(),
+                    version: Some(tags.change_number),
+                })
+            }
+        }
+    }
+}
+
+/// The downloaded ranges of a feed
+struct Fetched {
+    ranges: TrustedProxies,
+    /// The publication time of feeds that include one, to ignore outdated copies served by a cache
+    version: Option<u64>,
+}
+
+impl Fetched {
+    fn new(ranges: TrustedProxies) -> Self {
+        Fetched {
+            ranges,
+            version: None,
+        }
+    }
+
+    fn is_older_than(&self, other: &Fetched) -> bool {
+        matches!((self.version, other.version), (Some(new), Some(old)) if new < old)
+    }
+}
+
+async fn get(client: &Client, url: &str) -> io::Result<String> {
+    client
+        .get(url)
+        .timeout(TIMEOUT)
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .map_err(io::Error::other)?
+        .text()
+        .await
+        .map_err(io::Error::other)
+}
+
+fn parse_list(list: &str) -> io::Result<TrustedProxies> {
+    TrustedProxies::parse_list(list).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn parse_json<'a, T: Deserialize<'a>>(json: &'a str) -> io::Result<T> {
+    serde_json::from_str(json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[derive(Deserialize)]
+struct FastlyList {
+    addresses: Vec<IpNet>,
+    ipv6_addresses: Vec<IpNet>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleList {
+    sync_token: String,
+    prefixes: Vec<GooglePrefix>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GooglePrefix {
+    ipv4_prefix: Option<IpNet>,
+    ipv6_prefix: Option<IpNet>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzureServiceTags {
+    change_number: u64,
+    values: Vec<AzureServiceTag>,
+}
+
+#[derive(Deserialize)]
+struct AzureServiceTag {
+    name: String,
+    properties: AzureServiceTagProperties,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzureServiceTagProperties {
+    address_prefixes: Vec<IpNet>,
+}
+
+/// Refreshes the trusted proxies of a [`SharedResolver`] from published range feeds, see [`SharedResolver::refresh_ranges`].
+///
+/// The ranges are refreshed until this is dropped, after which their addresses are no longer trusted.
+///
+/// Requires the `fetch` feature.
+#[derive(Debug)]
+pub struct ProxyRangeRefresher {
+    task: JoinHandle<()>,
+    resolver: Weak<SharedResolver>,
+    source: SourceId,
+}
+
+impl Drop for ProxyRangeRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
+        if let Some(resolver) = self.resolver.upgrade() {
+            resolver.remove_source(self.source);
+        }
+    }
+}
+
+impl SharedResolver {
+    /// Trust the ranges from published feeds in addition to the configured trusted proxies,
+    /// downloading them again every `interval`.
+    ///
+    /// Downloading the feeds when starting has to succeed, when a later download fails the previously downloaded
+    /// ranges of that feed are kept. Ranges are also kept when a feed that includes its publication time,
+    /// like AWS's `ip-ranges.json`, returns an older copy than the one downloaded before.
+    ///
+    /// The ranges are refreshed by a task on the current tokio runtime.
+    /// Every refresh only replaces the ranges of these feeds, the configured trusted proxies and the networks of other
+    /// sources like `trust_hostnames` stay trusted.
+    ///
+    /// Requires the `fetch` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// # use real_ip::{RangeFeed, SharedResolver};
+    /// #
+    /// # async fn example() -> std::io::Result<()> {
+    /// let resolver = Arc::new(SharedResolver::new([]));
+    /// let _refresher = resolver
+    ///     .refresh_ranges([RangeFeed::Cloudflare], Duration::from_secs(24 * 60 * 60))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn refresh_ranges(
+        self: &Arc<Self>,
+        feeds: impl IntoIterator<Item = RangeFeed>,
+        interval: Duration,
+    ) -> io::Result<ProxyRangeRefresher> {
+        let client = Client::new();
+        let feeds: Vec<RangeFeed> = feeds.into_iter().collect();
+        let mut ranges = Vec::with_capacity(feeds.len());
+        for feed in &feeds {
+            ranges.push(feed.fetch_versioned(&client).await?);
+        }
+        let source = self.add_source();
+        self.update_source(source, trusted_proxies(&ranges));
+
+        let resolver = Arc::clone(self);
+        let task = tokio::spawn(async move {
+            let mut timer = interval_at(Instant::now() + interval, interval);
+            loop {
+                timer.tick().await;
+                for (feed, ranges) in feeds.iter().zip(ranges.iter_mut()) {
+                    match feed.fetch_versioned(&client).await {
+                        Ok(fetched) if !fetched.is_older_than(ranges) => *ranges = fetched,
+                        _ => {}
+                    }
+                }
+                resolver.update_source(source, trusted_proxies(&ranges));
+            }
+        });
+        Ok(ProxyRangeRefresher {
+            task,
+            resolver: Arc::downgrade(self),
+            source,
+        })
+    }
+}
+
+fn trusted_proxies(ranges: &[Fetched]) -> impl Iterator<Item = IpNet> + '_ {
+    ranges
+        .iter()
+        .flat_map(|fetched| fetched.ranges.networks().iter().copied())
+}
@@ -0,0 +1,466 @@
+use crate::headers::{
+    parse_cloudfront_viewer_address_header, parse_forwarded_header, parse_real_ip_header,
+    parse_x_forwarded_for_header, Element,
+};
+#[cfg(feature = "std")]
+use crate::RealIpSource;
+use alloc::string::String;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use core::iter::once;
+use core::net::IpAddr;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use http::HeaderName;
+use itertools::Either;
+
+/// The headers that can be used to determine the forwarded-for chain
+///
+/// Only the [`DEFAULT_HEADERS`] are read by default, the other headers have to be enabled with
+/// `RealIpConfigBuilder::headers`.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{ForwardingHeader, RealIpConfig};
+/// #
+/// let request = Request::builder().header("x-client-ip", "192.0.2.1").body(()).unwrap();
+///
+/// let config = RealIpConfig::builder().trusted_proxy(IpAddr::from([10, 0, 0, 1]).into()).build();
+/// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+///
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .headers([ForwardingHeader::XClientIp])
+///     .build();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ForwardingHeader {
+    /// The standardized `forwarded` header from [RFC 7239](https://www.rfc-editor.org/rfc/rfc7239)
+    Forwarded,
+    /// The `x-forwarded-for` header
+    XForwardedFor,
+    /// The `x-real-ip` header
+    XRealIp,
+    /// The `true-client-ip` header set by Akamai and Cloudflare Enterprise, containing a single address
+    ///
+    /// Like the other headers, it is only used when the proxy that sent it is trusted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([ForwardingHeader::TrueClientIp, ForwardingHeader::XForwardedFor])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("true-client-ip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 1])), config.resolve(&request, IpAddr::from([203, 0, 113, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "198.51.100.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    TrueClientIp,
+    /// The `fly-client-ip` header set by the Fly.io proxy, containing a single address
+    ///
+    /// The header can be used without `Provider::Fly`, for example when the Fly proxy is reached through
+    /// a proxy of your own:
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([ForwardingHeader::FlyClientIp])
+    ///     .build();
+    ///
+    /// let request = Request::builder().header("fly-client-ip", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 1])), config.resolve(&request, IpAddr::from([203, 0, 113, 1])));
+    /// ```
+    FlyClientIp,
+    /// The `x-vercel-forwarded-for` header set by the Vercel proxy, containing a single address
+    XVercelForwardedFor,
+    /// The `cf-connecting-ip` header set by Cloudflare, containing a single address
+    ///
+    /// The header is only used for requests from the configured Cloudflare ranges,
+    /// see [`RealIpConfigBuilder::cloudflare_proxies`](crate::RealIpConfigBuilder::cloudflare_proxies).
+    CfConnectingIp,
+    /// The `cloudfront-viewer-address` header set by AWS CloudFront, containing the address and port of the client
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::{IpAddr, SocketAddr};
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([ForwardingHeader::CloudFrontViewerAddress])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("cloudfront-viewer-address", "2001:db8::1:46532")
+    ///     .body(())
+    ///     .unwrap();
+    /// let client = config.resolve_with_port(&request, IpAddr::from([10, 0, 0, 1])).unwrap();
+    /// assert_eq!(Some("[2001:db8::1]:46532".parse::<SocketAddr>().unwrap()), client.socket_addr());
+    /// ```
+    CloudFrontViewerAddress,
+    /// The `x-azure-clientip` header set by Azure Front Door, containing a single address
+    ///
+    /// Front Door takes this address from an `x-forwarded-for` header sent by the client when present,
+    /// so it can be spoofed by the client.
+    XAzureClientIp,
+    /// The `x-azure-socketip` header set by Azure Front Door, containing the address the connection to Front Door
+    /// came from
+    XAzureSocketIp,
+    /// The `x-client-ip` header set by some proxies and api gateways, containing a single address
+    XClientIp,
+    /// The legacy `client-ip` header set by some older load balancers, containing a single address
+    ClientIp,
+    /// The `x-cluster-client-ip` header set by Rackspace load balancers and some Kubernetes ingress controllers,
+    /// containing a single address
+    XClusterClientIp,
+    /// The `x-appengine-user-ip` header set by the Google App Engine frontend, containing a single address
+    XAppengineUserIp,
+    /// The `x-envoy-external-address` header set by Envoy, containing the client address computed by Envoy
+    ///
+    /// With [`validate_consistency`](crate::RealIpConfigBuilder::validate_consistency) the header has to contain the
+    /// client address resolved from the other headers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig, RealIpError};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([ForwardingHeader::XForwardedFor, ForwardingHeader::XEnvoyExternalAddress])
+    ///     .validate_consistency(true)
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("x-forwarded-for", "192.0.2.1")
+    ///     .header("x-envoy-external-address", "192.0.2.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Ok(IpAddr::from([192, 0, 2, 1])), config.try_resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// // envoy computed a different client address
+    /// let request = Request::builder()
+    ///     .header("x-forwarded-for", "198.51.100.1, 192.0.2.1")
+    ///     .header("x-envoy-external-address", "198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     Err(RealIpError::ConflictingHeaders {
+    ///         header: ForwardingHeader::XForwardedFor,
+    ///         conflicting: ForwardingHeader::XEnvoyExternalAddress,
+    ///     }),
+    ///     config.try_resolve(&request, IpAddr::from([10, 0, 0, 1]))
+    /// );
+    /// ```
+    XEnvoyExternalAddress,
+    /// The non-standard `forwarded-for` header emitted by some middleboxes, in the same format as `x-forwarded-for`
+    ForwardedFor,
+    /// The `x-original-forwarded-for` header, containing the original `x-forwarded-for` chain preserved by a proxy
+    /// that replaced the header
+    ///
+    /// Besides using it like the other headers, the chain can continue from `x-forwarded-for` into this header,
+    /// see `RealIpConfigBuilder::original_forwarded_for_proxies`.
+    XOriginalForwardedFor,
+    /// The `fastly-client-ip` header set by Fastly, containing a single address
+    ///
+    /// Fastly keeps a `fastly-client-ip` header sent by the client unless the service overwrites it in VCL,
+    /// so only enable this header when the service sets it with `set req.http.Fastly-Client-IP = client.ip;`.
+    /// When that is the case it is more reliable than the `x-forwarded-for` chain, which is appended to by every shield.
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([ForwardingHeader::FastlyClientIp, ForwardingHeader::XForwardedFor])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("fastly-client-ip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "192.0.2.1, 198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    FastlyClientIp,
+    /// A header that isn't known to this crate, see [`ForwardingHeader::custom`]
+    Custom(CustomHeader),
+}
+
+impl ForwardingHeader {
+    /// The name of the header
+    pub fn name(&self) -> &str {
+        match self {
+            ForwardingHeader::Forwarded => "forwarded",
+            ForwardingHeader::XForwardedFor => "x-forwarded-for",
+            ForwardingHeader::XRealIp => "x-real-ip",
+            ForwardingHeader::TrueClientIp => "true-client-ip",
+            ForwardingHeader::FlyClientIp => "fly-client-ip",
+            ForwardingHeader::XVercelForwardedFor => "x-vercel-forwarded-for",
+            ForwardingHeader::CfConnectingIp => "cf-connecting-ip",
+            ForwardingHeader::CloudFrontViewerAddress => "cloudfront-viewer-address",
+            ForwardingHeader::XAzureClientIp => "x-azure-clientip",
+            ForwardingHeader::XAzureSocketIp => "x-azure-socketip",
+            ForwardingHeader::XClientIp => "x-client-ip",
+            ForwardingHeader::ClientIp => "client-ip",
+            ForwardingHeader::XClusterClientIp => "x-cluster-client-ip",
+            ForwardingHeader::XAppengineUserIp => "x-appengine-user-ip",
+            ForwardingHeader::XEnvoyExternalAddress => "x-envoy-external-address",
+            ForwardingHeader::FastlyClientIp => "fastly-client-ip",
+            ForwardingHeader::ForwardedFor => "forwarded-for",
+            ForwardingHeader::XOriginalForwardedFor => "x-original-forwarded-for",
+            ForwardingHeader::Custom(custom) => &custom.name,
+        }
+    }
+
+    /// A header that isn't known to this crate, containing addresses in the given format
+    ///
+    /// Like the other headers it has to be enabled with `RealIpConfigBuilder::headers`, in the position that
+    /// matches its priority. Custom headers can't be loaded from a configuration file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::{HeaderName, Request};
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ForwardingHeader, HeaderFormat, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+    ///     .headers([
+    ///         ForwardingHeader::custom(HeaderName::from_static("x-origin-client"), HeaderFormat::SocketAddress),
+    ///         ForwardingHeader::XForwardedFor,
+    ///     ])
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("x-origin-client", "2001:db8::1:46532")
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some("2001:db8::1".parse().unwrap()), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "198.51.100.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn custom(name: HeaderName, format: HeaderFormat) -> Self {
+        ForwardingHeader::Custom(CustomHeader {
+            name: name.as_str().into(),
+            format,
+        })
+    }
+
+    /// Whether the header contains only the address of the client instead of the full chain
+    #[cfg(feature = "std")]
+    pub(crate) fn is_single_address(&self) -> bool {
+        matches!(
+            self,
+            ForwardingHeader::XRealIp
+                | ForwardingHeader::TrueClientIp
+                | ForwardingHeader::FlyClientIp
+                | ForwardingHeader::XVercelForwardedFor
+                | ForwardingHeader::CfConnectingIp
+                | ForwardingHeader::CloudFrontViewerAddress
+                | ForwardingHeader::XAzureClientIp
+                | ForwardingHeader::XAzureSocketIp
+                | ForwardingHeader::XClientIp
+                | ForwardingHeader::ClientIp
+                | ForwardingHeader::XClusterClientIp
+                | ForwardingHeader::XAppengineUserIp
+                | ForwardingHeader::XEnvoyExternalAddress
+                | ForwardingHeader::FastlyClientIp
+                | ForwardingHeader::Custom(CustomHeader {
+                    format: HeaderFormat::SingleAddress | HeaderFormat::SocketAddress,
+                    ..
+                })
+        )
+    }
+
+    /// Get the list of ip addresses from a value of this header
+    pub fn extract<'a>(
+        &self,
+        header_value: &'a str,
+    ) -> impl DoubleEndedIterator<Item = IpAddr> + 'a {
+        self.parse(header_value).filter_map(Element::ip)
+    }
+
+    /// Parse the elements from a value of this header
+    pub(crate) fn parse<'a>(
+        &self,
+        header_value: &'a str,
+    ) -> impl DoubleEndedIterator<Item = Element<'a>> + 'a {
+        match self {
+            ForwardingHeader::Forwarded => {
+                Either::Left(Either::Left(parse_forwarded_header(header_value)))
+            }
+            ForwardingHeader::XForwardedFor
+            | ForwardingHeader::ForwardedFor
+            | ForwardingHeader::XOriginalForwardedFor
+            | ForwardingHeader::Custom(CustomHeader {
+                format: HeaderFormat::AddressList,
+                ..
+            }) => Either::Left(Either::Right(parse_x_forwarded_for_header(header_value))),
+            ForwardingHeader::XRealIp
+            | ForwardingHeader::TrueClientIp
+            | ForwardingHeader::FlyClientIp
+            | ForwardingHeader::XVercelForwardedFor
+            | ForwardingHeader::CfConnectingIp
+            | ForwardingHeader::XAzureClientIp
+            | ForwardingHeader::XAzureSocketIp
+            | ForwardingHeader::XClientIp
+            | ForwardingHeader::ClientIp
+            | ForwardingHeader::XClusterClientIp
+            | ForwardingHeader::XAppengineUserIp
+            | ForwardingHeader::XEnvoyExternalAddress
+            | ForwardingHeader::FastlyClientIp
+            | ForwardingHeader::Custom(CustomHeader {
+                format: HeaderFormat::SingleAddress,
+                ..
+            }) => Either::Right(Either::Left(parse_real_ip_header(header_value))),
+            ForwardingHeader::CloudFrontViewerAddress
+            | ForwardingHeader::Custom(CustomHeader {
+                format: HeaderFormat::SocketAddress,
+                ..
+            }) => Either::Right(Either::Right(parse_cloudfront_viewer_address_header(
+                header_value,
+            ))),
+        }
+    }
+
+    /// Get the list of ip addresses from all instances of this header in a request
+    ///
+    /// Multiple instances of the header are treated as a single comma separated value, in the order they appear in the request.
+    #[cfg(feature = "std")]
+    pub fn extract_all<'a, S: RealIpSource + ?Sized>(
+        &'a self,
+        source: &'a S,
+    ) -> impl DoubleEndedIterator<Item = IpAddr> + 'a {
+        self.parse_all(source).filter_map(Element::ip)
+    }
+
+    /// Parse the elements from all instances of this header
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_all<'a, S: RealIpSource + ?Sized>(
+        &'a self,
+        source: &'a S,
+    ) -> impl DoubleEndedIterator<Item = Element<'a>> + 'a {
+        source
+            .header(self.name())
+            .flat_map(move |value| match value.to_str() {
+                Ok(value) => Either::Left(self.parse(value)),
+                Err(_) => Either::Right(once(Element::Malformed)),
+            })
+    }
+}
+
+impl Display for ForwardingHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ForwardingHeader {
+    type Err = UnknownHeaderError;
+
+    /// Parse a header from its (case-insensitive) name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "forwarded" => Ok(ForwardingHeader::Forwarded),
+            "x-forwarded-for" => Ok(ForwardingHeader::XForwardedFor),
+            "x-real-ip" => Ok(ForwardingHeader::XRealIp),
+            "true-client-ip" => Ok(ForwardingHeader::TrueClientIp),
+            "fly-client-ip" => Ok(ForwardingHeader::FlyClientIp),
+            "x-vercel-forwarded-for" => Ok(ForwardingHeader::XVercelForwardedFor),
+            "cf-connecting-ip" => Ok(ForwardingHeader::CfConnectingIp),
+            "cloudfront-viewer-address" => Ok(ForwardingHeader::CloudFrontViewerAddress),
+            "x-azure-clientip" => Ok(ForwardingHeader::XAzureClientIp),
+            "x-azure-socketip" => Ok(ForwardingHeader::XAzureSocketIp),
+            "x-client-ip" => Ok(ForwardingHeader::XClientIp),
+            "client-ip" => Ok(ForwardingHeader::ClientIp),
+            "x-cluster-client-ip" => Ok(ForwardingHeader::XClusterClientIp),
+            "x-appengine-user-ip" => Ok(ForwardingHeader::XAppengineUserIp),
+            "x-envoy-external-address" => Ok(ForwardingHeader::XEnvoyExternalAddress),
+            "fastly-client-ip" => Ok(ForwardingHeader::FastlyClientIp),
+            "forwarded-for" => Ok(ForwardingHeader::ForwardedFor),
+            "x-original-forwarded-for" => Ok(ForwardingHeader::XOriginalForwardedFor),
+            _ => Err(UnknownHeaderError(s.into())),
+        }
+    }
+}
+
+/// A forwarding header that isn't known to this crate, created with [`ForwardingHeader::custom`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomHeader {
+    /// The lowercase name of the header
+    name: String,
+    format: HeaderFormat,
+}
+
+impl CustomHeader {
+    /// The format of the addresses in the header
+    pub fn format(&self) -> HeaderFormat {
+        self.format
+    }
+}
+
+/// The format of a custom forwarding header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeaderFormat {
+    /// A single address, with optional port, like `x-real-ip`
+    SingleAddress,
+    /// A comma separated list of addresses starting at the client, like `x-forwarded-for`
+    AddressList,
+    /// A single address and port, like `cloudfront-viewer-address`
+    ///
+    /// IPv6 addresses don't need to be bracketed, the port is always the part after the last colon.
+    SocketAddress,
+}
+
+/// Error returned when parsing an unsupported forwarding header name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownHeaderError(pub String);
+
+impl Display for UnknownHeaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "unknown forwarding header: {}", self.0)
+    }
+}
+
+impl Error for UnknownHeaderError {}
+
+/// The headers that are used by default, in order of preference
+pub const DEFAULT_HEADERS: [ForwardingHeader; 3] = [
+    ForwardingHeader::Forwarded,
+    ForwardingHeader::XForwardedFor,
+    ForwardingHeader::XRealIp,
+];
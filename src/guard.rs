@@ -0,0 +1,92 @@
+use crate::{ClientIp, ClientIpRejection, RealIpConfig, Resolver};
+use rocket::http::{HeaderMap, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Get the "real-ip" of the request in a Rocket route, with the [`RealIpConfig`] or [`Resolver`] managed by Rocket
+///
+/// Unlike `Request::client_ip` the forwarding headers are only used when they're set by a trusted proxy.
+/// The address is resolved once per request, requests for which the address can't be determined fail with
+/// `400 Bad Request`, requests without managed configuration with `500 Internal Server Error`.
+///
+/// Requires the `rocket` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # use rocket::{get, routes};
+/// # use rocket::local::blocking::Client;
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// #[get("/")]
+/// fn index(ip: ClientIp) -> String {
+///     ip.0.to_string()
+/// }
+///
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let rocket = rocket::build().manage(config).mount("/", routes![index]);
+///
+/// let client = Client::tracked(rocket).unwrap();
+/// let response = client
+///     .get("/")
+///     .remote(SocketAddr::from(([10, 0, 0, 1], 46532)))
+///     .header(rocket::http::Header::new("x-forwarded-for", "192.0.2.1"))
+///     .dispatch();
+/// assert_eq!("192.0.2.1", response.into_string().unwrap());
+///
+/// // the same headers from a client that isn't trusted
+/// let response = client
+///     .get("/")
+///     .remote(SocketAddr::from(([203, 0, 113, 10], 46532)))
+///     .header(rocket::http::Header::new("x-forwarded-for", "192.0.2.1"))
+///     .dispatch();
+/// assert_eq!("203.0.113.10", response.into_string().unwrap());
+/// ```
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = ClientIpRejection;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let result = request.local_cache(|| {
+            let remote = request
+                .remote()
+                .ok_or(ClientIpRejection::MissingConnectInfo)?;
+            let headers = to_http_headers(request.headers());
+            let rocket = request.rocket();
+            let decision = if let Some(resolver) = rocket.state::<Resolver>() {
+                resolver.resolve_with_report(&headers, remote)
+            } else if let Some(config) = rocket.state::<RealIpConfig>() {
+                config.resolve_with_report(&headers, remote)
+            } else {
+                return Err(ClientIpRejection::MissingConfig);
+            };
+            decision
+                .ip
+                .map(ClientIp)
+                .ok_or(ClientIpRejection::Unresolved(decision.error))
+        });
+        match result {
+            Ok(ip) => Outcome::Success(*ip),
+            Err(rejection) => {
+                let status = Status::from_code(rejection.status().as_u16())
+                    .unwrap_or(Status::InternalServerError);
+                Outcome::Error((status, rejection.clone()))
+            }
+        }
+    }
+}
+
+/// Copy the headers of a Rocket request into the `http` types used by this crate
+fn to_http_headers(headers: &HeaderMap<'_>) -> http::HeaderMap {
+    headers
+        .iter()
+        .filter_map(|header| {
+            Some((
+                http::HeaderName::from_bytes(header.name().as_str().as_bytes()).ok()?,
+                http::HeaderValue::from_str(header.value()).ok()?,
+            ))
+        })
+        .collect()
+}
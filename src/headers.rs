@@ -1,9 +1,12 @@
-use comma_separated::CommaSeparatedIterator;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::iter::once;
+use core::net::{IpAddr, SocketAddr};
+use core::str::FromStr;
 use rfc7239::{parse, Forwarded, NodeIdentifier, NodeName};
-use std::borrow::Cow;
-use std::iter::IntoIterator;
-use std::net::IpAddr;
-use std::str::FromStr;
 
 /// Get the list of ip addresses from an `forwarded` header
 ///
@@ -22,16 +25,135 @@ use std::str::FromStr;
 pub fn extract_forwarded_header(
     header_value: &str,
 ) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
-    parse(header_value).filter_map(|forward| match forward {
-        Ok(Forwarded {
+    parse_forwarded_header(header_value).filter_map(Element::ip)
+}
+
+/// Get the list of `for` nodes from a `forwarded` header, including obfuscated and unknown nodes
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec![Node::Obfuscated("_hidden".into()), Node::Unknown, Node::Ip(IpAddr::from([10, 10, 10, 20]))],
+///    extract_forwarded_nodes("for=_hidden, for=unknown, for=10.10.10.20;proto=https").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_forwarded_nodes(header_value: &str) -> impl DoubleEndedIterator<Item = Node> + '_ {
+    parse_forwarded_header(header_value).filter_map(Element::node)
+}
+
+/// Parse the `for` addresses from a `forwarded` header
+pub(crate) fn parse_forwarded_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = Element<'_>> + '_ {
+    split_elements(header_value).filter_map(|element| {
+        if element.is_empty() {
+            return Some(Element::Empty);
+        }
+        match parse(element).next() {
+            Some(Ok(forwarded)) => forwarded_element(forwarded),
+            _ => parse_zoned_forwarded_element(element),
+        }
+    })
+}
+
+/// Convert a parsed `forwarded` element, ignoring elements without `for` node
+fn forwarded_element(forwarded: Forwarded<'_>) -> Option<Element<'_>> {
+    match forwarded {
+        Forwarded {
             forwarded_for:
                 Some(NodeIdentifier {
-                    name: NodeName::Ip(ip),
+                    name: NodeName::Unknown,
+                    ..
+                }),
+            ..
+        } => Some(Element::Unknown),
+        Forwarded {
+            forwarded_for:
+                Some(NodeIdentifier {
+                    name: NodeName::Obfuscated(name),
                     ..
                 }),
             ..
-        }) => Some(ip),
+        } => Some(Element::Obfuscated(name)),
+        Forwarded {
+            forwarded_for:
+                Some(NodeIdentifier {
+                    name: NodeName::Ip(ip),
+                    port,
+                }),
+            forwarded_by,
+            protocol,
+            host,
+        } => Some(Element::Ip {
+            ip,
+            port,
+            protocol,
+            host,
+            by: forwarded_by.map(|by| match by.name {
+                NodeName::Ip(ip) => Cow::Owned(ip.to_string()),
+                NodeName::Unknown => Cow::Borrowed("unknown"),
+                NodeName::Obfuscated(name) => Cow::Borrowed(name),
+            }),
+            zone: None,
+        }),
         _ => None,
+    }
+}
+
+/// Parse a `forwarded` element where the `for` or `by` address contains an IPv6 zone id
+fn parse_zoned_forwarded_element(element: &str) -> Option<Element<'_>> {
+    let mut zone = None;
+    let mut stripped = Vec::new();
+    for part in element.split(';') {
+        let part = match part.split_once('=') {
+            Some((key, value))
+                if key.eq_ignore_ascii_case("for") || key.eq_ignore_ascii_case("by") =>
+            {
+                match strip_zone(value) {
+                    Some((value, part_zone)) => {
+                        if key.eq_ignore_ascii_case("for") {
+                            zone = Some(part_zone);
+                        }
+                        Cow::Owned(format!("{}={}", key, value))
+                    }
+                    None => Cow::Borrowed(part),
+                }
+            }
+            _ => Cow::Borrowed(part),
+        };
+        stripped.push(part);
+    }
+    let stripped = stripped.join(";");
+    let parsed = match parse(&stripped).next() {
+        Some(Ok(forwarded)) => match forwarded_element(forwarded)? {
+            Element::Ip { ip, port, by, .. } => Some(Element::Ip {
+                ip,
+                port,
+                // the protocol and host are unchanged, so they can be taken from the original element
+                protocol: forwarded_param(element, "proto"),
+                host: forwarded_param(element, "host"),
+                by: by.map(|by| Cow::Owned(by.into_owned())),
+                zone: zone.map(Cow::Owned),
+            }),
+            _ => None,
+        },
+        _ => Some(Element::Malformed),
+    };
+    parsed
+}
+
+/// The value of a parameter of a `forwarded` element
+fn forwarded_param<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    element.split(';').find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if key.eq_ignore_ascii_case(name) {
+            Some(value)
+        } else {
+            None
+        }
     })
 }
 
@@ -50,9 +172,32 @@ pub fn extract_forwarded_header(
 pub fn extract_x_forwarded_for_header(
     header_value: &str,
 ) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
-    CommaSeparatedIterator::new(header_value)
-        .map(str::trim)
-        .flat_map(|x| IpAddr::from_str(maybe_bracketed(&maybe_quoted(x))))
+    parse_x_forwarded_for_header(header_value).filter_map(Element::ip)
+}
+
+/// Get the list of ip addresses from a non-standard `forwarded-for` header, which uses the `x-forwarded-for` format
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec![IpAddr::from([10, 10, 10, 10]), IpAddr::from([10, 10, 10, 20])],
+///    extract_forwarded_for_header("10.10.10.10, 10.10.10.20").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_forwarded_for_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
+    parse_x_forwarded_for_header(header_value).filter_map(Element::ip)
+}
+
+/// Parse the addresses from an `x-forwarded-for` header
+pub(crate) fn parse_x_forwarded_for_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = Element<'_>> + '_ {
+    split_elements(header_value).map(parse_ip_element)
 }
 
 /// Get the list of ip addresses from an `x-real-ip` header
@@ -68,7 +213,485 @@ pub fn extract_x_forwarded_for_header(
 /// );
 /// ```
 pub fn extract_real_ip_header(header_value: &str) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
-    IpAddr::from_str(maybe_bracketed(&maybe_quoted(header_value))).into_iter()
+    parse_real_ip_header(header_value).filter_map(Element::ip)
+}
+
+/// Get the ip address from a `true-client-ip` header
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec![IpAddr::from([192, 0, 2, 1])],
+///    extract_true_client_ip_header(" 192.0.2.1 ").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_true_client_ip_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
+    parse_real_ip_header(header_value).filter_map(Element::ip)
+}
+
+/// Get the ip address from a `fly-client-ip` header
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec![IpAddr::from([192, 0, 2, 1])],
+///    extract_fly_client_ip_header("192.0.2.1").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_fly_client_ip_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
+    parse_real_ip_header(header_value).filter_map(Element::ip)
+}
+
+/// Get the address and port from a `cloudfront-viewer-address` header
+///
+/// CloudFront sends IPv6 addresses without brackets, the port is always the part after the last colon.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::SocketAddr;
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec!["198.51.100.10:46532".parse::<SocketAddr>().unwrap()],
+///    extract_cloudfront_viewer_address_header("198.51.100.10:46532").collect::<Vec<_>>()
+/// );
+/// assert_eq!(
+///    vec!["[2001:db8::1]:46532".parse::<SocketAddr>().unwrap()],
+///    extract_cloudfront_viewer_address_header("2001:db8::1:46532").collect::<Vec<_>>()
+/// );
+/// assert_eq!(
+///    vec!["[2001:db8::1]:46532".parse::<SocketAddr>().unwrap()],
+///    extract_cloudfront_viewer_address_header("[2001:db8::1]:46532").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_cloudfront_viewer_address_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = SocketAddr> + '_ {
+    parse_cloudfront_viewer_address_header(header_value).filter_map(|element| match element {
+        Element::Ip {
+            ip,
+            port: Some(port),
+            ..
+        } => Some(SocketAddr::new(ip, port)),
+        _ => None,
+    })
+}
+
+/// Parse the address and port from a `cloudfront-viewer-address` header
+pub(crate) fn parse_cloudfront_viewer_address_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = Element<'_>> + '_ {
+    let value = header_value.trim();
+    let element = match value.rsplit_once(':') {
+        Some((ip, port)) if !value.starts_with('[') => {
+            match (IpAddr::from_str(ip), u16::from_str(port)) {
+                (Ok(ip), Ok(port)) => Element::Ip {
+                    ip,
+                    port: Some(port),
+                    protocol: None,
+                    host: None,
+                    by: None,
+                    zone: None,
+                },
+                _ => Element::Malformed,
+            }
+        }
+        _ => parse_ip_element(value),
+    };
+    once(element)
+}
+
+/// Get the ports from an `x-forwarded-port` header, invalid values are skipped
+///
+/// # Example
+///
+/// ```rust
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec![443, 8080],
+///    extract_x_forwarded_port_header("443, 8080, invalid").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_x_forwarded_port_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = u16> + '_ {
+    header_value
+        .split(',')
+        .filter_map(|port| u16::from_str(port.trim()).ok())
+}
+
+/// Get the protocols from an `x-forwarded-proto` header, lowercased, values that aren't a valid uri scheme are skipped
+///
+/// Note that this doesn't perform any validation against clients forging the header,
+/// use `forwarded_proto` to only accept a protocol reported by a trusted proxy.
+///
+/// # Example
+///
+/// ```rust
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec!["https", "http"],
+///    extract_x_forwarded_proto_header("HTTPS, http, ht/tp").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_x_forwarded_proto_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = String> + '_ {
+    header_value
+        .split(',')
+        .filter_map(|proto| normalize_proto(proto.trim()))
+}
+
+/// Lowercase a protocol, ignoring values that aren't a valid uri scheme
+pub(crate) fn normalize_proto(proto: &str) -> Option<String> {
+    let proto = proto.trim_matches('"');
+    let mut chars = proto.chars();
+    let valid = chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if valid {
+        Some(proto.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Get the hosts from an `x-forwarded-host` header, lowercased, values that contain characters not allowed in a
+/// uri authority are skipped
+///
+/// Note that this doesn't perform any validation against clients forging the header,
+/// use `forwarded_host` to only accept a host reported by a trusted proxy.
+///
+/// # Example
+///
+/// ```rust
+/// # use real_ip::headers::*;
+/// assert_eq!(
+///    vec!["example.com", "[2001:db8::1]:8080"],
+///    extract_x_forwarded_host_header("Example.com, [2001:db8::1]:8080, evil.com/path").collect::<Vec<_>>()
+/// );
+/// ```
+pub fn extract_x_forwarded_host_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = String> + '_ {
+    header_value
+        .split(',')
+        .filter_map(|host| normalize_host(host.trim()))
+}
+
+/// Lowercase a host, ignoring values that contain characters not allowed in a uri authority
+pub(crate) fn normalize_host(host: &str) -> Option<String> {
+    let host = host.trim_matches('"');
+    let valid = !host.is_empty()
+        && host.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~' | ':' | '[' | ']')
+        });
+    if valid {
+        Some(host.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Get the proxies from a `via` header, in the order the request passed through them
+///
+/// The `via` header doesn't contain any client address, but it identifies the proxies that handled the request,
+/// entries that are not of the form `[protocol/]version received-by [comment]` are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # use real_ip::headers::*;
+/// let entries = extract_via_header("1.1 vegur, HTTP/1.0 fred, 1.1 p.example.net:8080 (Proxy, version 2)")
+///     .collect::<Vec<_>>();
+/// assert_eq!(3, entries.len());
+/// assert_eq!("vegur", entries[0].received_by);
+/// assert_eq!(Some("HTTP"), entries[1].protocol.as_deref());
+/// assert_eq!("1.0", entries[1].version);
+/// assert_eq!("p.example.net:8080", entries[2].received_by);
+/// assert_eq!(Some("Proxy, version 2"), entries[2].comment.as_deref());
+/// ```
+pub fn extract_via_header(header_value: &str) -> impl Iterator<Item = ViaEntry> + '_ {
+    let mut depth = 0usize;
+    header_value
+        .split(move |c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            c == ',' && depth == 0
+        })
+        .filter_map(parse_via_entry)
+}
+
+fn parse_via_entry(entry: &str) -> Option<ViaEntry> {
+    let (protocol, rest) = entry.trim().split_once([' ', '\t'])?;
+    let rest = rest.trim_start();
+    let (received_by, comment) = match rest.split_once([' ', '\t']) {
+        Some((received_by, comment)) => (received_by, Some(comment.trim())),
+        None => (rest, None),
+    };
+    let comment = match comment {
+        Some(comment) => Some(comment.strip_prefix('(')?.strip_suffix(')')?.to_string()),
+        None => None,
+    };
+    let (protocol, version) = match protocol.split_once('/') {
+        Some((name, version)) => (Some(name.to_string()), version),
+        None => (None, protocol),
+    };
+    if version.is_empty() || received_by.is_empty() {
+        return None;
+    }
+    Some(ViaEntry {
+        protocol,
+        version: version.to_string(),
+        received_by: received_by.to_string(),
+        comment,
+    })
+}
+
+/// A proxy listed in a `via` header, as defined by [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-7.6.3)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ViaEntry {
+    /// The name of the protocol the proxy received the request with, `None` when omitted for http
+    pub protocol: Option<String>,
+    /// The version of the protocol, like `1.1`
+    pub version: String,
+    /// The host name or pseudonym of the proxy, including the port if one was provided
+    pub received_by: String,
+    /// The comment without parentheses, usually identifying the proxy software
+    pub comment: Option<String>,
+}
+
+/// Parse the address from an `x-real-ip` header
+pub(crate) fn parse_real_ip_header(
+    header_value: &str,
+) -> impl DoubleEndedIterator<Item = Element<'_>> + '_ {
+    once(parse_ip_element(header_value.trim()))
+}
+
+/// A single element of a forwarding header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Element<'a> {
+    /// An address with optional port and the protocol, host and receiving proxy of the request
+    Ip {
+        ip: IpAddr,
+        port: Option<u16>,
+        protocol: Option<&'a str>,
+        host: Option<&'a str>,
+        /// The name of the proxy that received the request, without port
+        by: Option<Cow<'a, str>>,
+        /// The zone id of an IPv6 address, like `eth0` in `fe80::1%eth0`
+        zone: Option<Cow<'a, str>>,
+    },
+    /// An obfuscated identifier like `_hidden`
+    Obfuscated(&'a str),
+    /// The `unknown` identifier
+    Unknown,
+    /// An empty or whitespace-only element, including a trailing comma
+    Empty,
+    Malformed,
+}
+
+impl Element<'_> {
+    pub(crate) fn ip(self) -> Option<IpAddr> {
+        match self {
+            Element::Ip { ip, .. } => Some(ip),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn node(self) -> Option<Node> {
+        match self {
+            Element::Ip { ip, .. } => Some(Node::Ip(ip)),
+            Element::Obfuscated(name) => Some(Node::Obfuscated(name.into())),
+            Element::Unknown => Some(Node::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// A node identifier from a forwarding header, as defined by [RFC 7239](https://www.rfc-editor.org/rfc/rfc7239#section-6)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// An ip address
+    Ip(IpAddr),
+    /// An obfuscated identifier like `_hidden`, used by proxies that don't want to reveal the address
+    ///
+    /// Obfuscated identifiers can be stable, so they can be used to correlate requests.
+    Obfuscated(String),
+    /// The `unknown` identifier, used when the proxy doesn't know the address of the previous hop
+    Unknown,
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Node::Ip(ip) => write!(f, "{}", ip),
+            Node::Obfuscated(name) => f.write_str(name),
+            Node::Unknown => f.write_str("unknown"),
+        }
+    }
+}
+
+fn parse_ip_element(x: &str) -> Element<'_> {
+    if x.is_empty() {
+        return Element::Empty;
+    }
+    if x.eq_ignore_ascii_case("unknown") {
+        return Element::Unknown;
+    }
+    let x = maybe_quoted(x);
+    match parse_addr(&x) {
+        Some((ip, port)) => Element::Ip {
+            ip,
+            port,
+            protocol: None,
+            host: None,
+            by: None,
+            zone: None,
+        },
+        None => match strip_zone(&x) {
+            Some((x, zone)) => match parse_addr(&x) {
+                Some((ip @ IpAddr::V6(_), port)) => Element::Ip {
+                    ip,
+                    port,
+                    protocol: None,
+                    host: None,
+                    by: None,
+                    zone: Some(Cow::Owned(zone)),
+                },
+                _ => Element::Malformed,
+            },
+            None => Element::Malformed,
+        },
+    }
+}
+
+/// Parse an address with optional port
+fn parse_addr(x: &str) -> Option<(IpAddr, Option<u16>)> {
+    if let Ok(ip) = IpAddr::from_str(maybe_bracketed(x)) {
+        return Some((ip, None));
+    }
+    let addr = SocketAddr::from_str(x).ok()?;
+    Some((addr.ip(), Some(addr.port())))
+}
+
+/// Remove the zone id from an address like `fe80::1%eth0` or `"[fe80::1%25eth0]:8080"`, returning the address and zone
+///
+/// A `%25` delimiter, the percent encoded form used in uris, is also accepted.
+fn strip_zone(x: &str) -> Option<(String, String)> {
+    let start = x.find('%')?;
+    let end = x[start..]
+        .find([']', '"'])
+        .map_or(x.len(), |end| start + end);
+    let zone = &x[start + 1..end];
+    let zone = match zone.strip_prefix("25") {
+        Some(decoded) if !decoded.is_empty() => decoded,
+        _ => zone,
+    };
+    let valid = !zone.is_empty()
+        && zone
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'));
+    if valid {
+        Some((format!("{}{}", &x[..start], &x[end..]), zone.into()))
+    } else {
+        None
+    }
+}
+
+/// Split a header into its trimmed comma separated elements, keeping empty elements
+fn split_elements(header_value: &str) -> impl DoubleEndedIterator<Item = &str> + '_ {
+    let trailing_empty = if header_value.trim().is_empty() || header_value.trim_end().ends_with(',')
+    {
+        Some("")
+    } else {
+        None
+    };
+    CommaSeparated {
+        remaining: Some(header_value),
+    }
+    .map(str::trim)
+    .chain(trailing_empty)
+}
+
+/// Iterator over the comma separated parts of a header, ignoring commas inside quoted strings
+struct CommaSeparated<'a> {
+    remaining: Option<&'a str>,
+}
+
+impl<'a> Iterator for CommaSeparated<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+        match first_unquoted_comma(remaining) {
+            Some(comma) => {
+                self.remaining = Some(&remaining[comma + 1..]);
+                Some(&remaining[..comma])
+            }
+            None => self.remaining.take().filter(|part| !part.is_empty()),
+        }
+    }
+}
+
+impl DoubleEndedIterator for CommaSeparated<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+        match last_unquoted_comma(remaining) {
+            Some(comma) => {
+                self.remaining = Some(&remaining[..comma]);
+                Some(&remaining[comma + 1..])
+            }
+            None => self.remaining.take().filter(|part| !part.is_empty()),
+        }
+    }
+}
+
+/// The byte offset of the first comma outside of a quoted string
+fn first_unquoted_comma(x: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in x.bytes().enumerate() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), b'\\') => escaped = true,
+            (Some(open), c) if c == open => quote = None,
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b',') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The byte offset of the last comma outside of a quoted string
+///
+/// When scanning backwards, a quote preceded by a backslash is taken to be escaped.
+fn last_unquoted_comma(x: &str) -> Option<usize> {
+    let bytes = x.as_bytes();
+    let mut quote = None;
+    for (i, &c) in bytes.iter().enumerate().rev() {
+        match (quote, c) {
+            (Some(open), c) if c == open && (i == 0 || bytes[i - 1] != b'\\') => quote = None,
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b',') => return Some(i),
+            _ => {}
+        }
+    }
+    None
 }
 
 enum EscapeState {
@@ -76,12 +699,17 @@ enum EscapeState {
     Escaped,
 }
 
-fn maybe_quoted(x: &str) -> Cow<str> {
-    let mut i = x.chars();
-    if i.next() == Some('"') {
-        let mut s = String::with_capacity(x.len());
+/// Remove the quotes from a quoted string, only allocating when it contains escaped characters
+fn maybe_quoted(x: &str) -> Cow<'_, str> {
+    if let Some(quoted) = x.strip_prefix('"') {
+        match quoted.find(['"', '\\']) {
+            Some(end) if quoted.as_bytes()[end] == b'"' => return quoted[..end].into(),
+            None => return quoted.into(),
+            Some(_) => {}
+        }
+        let mut s = String::with_capacity(quoted.len());
         let mut state = EscapeState::Normal;
-        for c in i {
+        for c in quoted.chars() {
             state = match state {
                 EscapeState::Normal => match c {
                     '"' => break,
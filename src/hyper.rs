@@ -0,0 +1,132 @@
+//! Service wrapper for using [hyper](https://hyper.rs) without a framework
+//!
+//! Hyper doesn't store the remote address of a connection in the requests, so the address has to be captured
+//! when accepting the connection by wrapping the service that handles it.
+//!
+//! Requires the `hyper` feature.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use http::{Request, Response};
+//! # use hyper::body::Incoming;
+//! # use hyper::server::conn::http1;
+//! # use hyper::service::service_fn;
+//! # use hyper_util::rt::TokioIo;
+//! # use std::convert::Infallible;
+//! # use std::net::IpAddr;
+//! # use std::sync::Arc;
+//! # use tokio::net::TcpListener;
+//! # use real_ip::hyper::RealIpService;
+//! # use real_ip::{ClientIp, RealIpConfig, Resolver};
+//! #
+//! async fn handle(request: Request<Incoming>) -> Result<Response<String>, Infallible> {
+//!     let ip = request.extensions().get::<ClientIp>().map(|ClientIp(ip)| ip.to_string());
+//!     Ok(Response::new(ip.unwrap_or_default()))
+//! }
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> std::io::Result<()> {
+//! let config = RealIpConfig::builder()
+//!     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+//!     .build();
+//! let resolver = Arc::new(Resolver::from(config));
+//!
+//! let listener = TcpListener::bind("0.0.0.0:8080").await?;
+//! loop {
+//!     let (stream, remote) = listener.accept().await?;
+//!     let service = RealIpService::new(service_fn(handle), resolver.clone(), remote);
+//!     tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+//! }
+//! # }
+//! ```
+
+use crate::{ClientIp, ConnectionInfo, Resolver};
+use http::Request;
+use hyper::service::Service;
+use std::sync::Arc;
+
+/// A [`Service`] that resolves the "real-ip" of every request on a connection and stores it in the request extensions
+///
+/// The [`ClientIp`] is stored for the wrapped service, together with the [`Decision`](crate::Decision) for anything
+/// that needs the full report, see [`Decision::cached`](crate::Decision::cached).
+///
+/// # Example
+///
+/// ```rust
+/// # use http::{Request, Response};
+/// # use hyper::service::{service_fn, Service};
+/// # use std::convert::Infallible;
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::hyper::RealIpService;
+/// # use real_ip::{ClientIp, Resolver};
+/// #
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let resolver = Resolver::new([IpAddr::from([10, 0, 0, 1]).into()]);
+/// let remote = SocketAddr::from(([10, 0, 0, 1], 46532));
+/// let service = RealIpService::new(
+///     service_fn(|request: Request<String>| async move {
+///         let ip = request.extensions().get::<ClientIp>().map(|ClientIp(ip)| ip.to_string());
+///         Ok::<_, Infallible>(Response::new(ip.unwrap_or_default()))
+///     }),
+///     resolver,
+///     remote,
+/// );
+///
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .body(String::new())
+///     .unwrap();
+/// let response = service.call(request).await.unwrap();
+/// assert_eq!("192.0.2.1", response.body());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RealIpService<S> {
+    inner: S,
+    resolver: Arc<Resolver>,
+    connection: ConnectionInfo,
+}
+
+impl<S> RealIpService<S> {
+    /// Wrap the service handling a connection from `remote`
+    ///
+    /// The resolver can be shared between connections by passing an `Arc<Resolver>`.
+    pub fn new(
+        inner: S,
+        resolver: impl Into<Arc<Resolver>>,
+        remote: impl Into<ConnectionInfo>,
+    ) -> Self {
+        RealIpService {
+            inner,
+            resolver: resolver.into(),
+            connection: remote.into(),
+        }
+    }
+
+    /// The wrapped service
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S, B> Service<Request<B>> for RealIpService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let ip = self
+            .resolver
+            .resolve_cached(&mut request, self.connection)
+            .ip;
+        if let Some(ip) = ip {
+            request.extensions_mut().insert(ClientIp(ip));
+        }
+        self.inner.call(request)
+    }
+}
@@ -0,0 +1,100 @@
+//! Support for [lambda_http](https://github.com/awslabs/aws-lambda-rust-runtime) requests from API Gateway
+//!
+//! Functions on Lambda don't have a connection with the client, instead API Gateway reports the address that
+//! connected to it as `sourceIp` in the request context. That address takes the place of the remote address, so the
+//! forwarding headers are only used when the `sourceIp` is a trusted proxy, like a CloudFront distribution in front
+//! of API Gateway.
+//!
+//! Requires the `lambda-http` feature.
+
+use crate::{ConnectionInfo, IpNet};
+use http::Extensions;
+use lambda_http::request::RequestContext;
+use lambda_http::{Request, RequestExt};
+use std::net::IpAddr;
+
+/// The `sourceIp` reported by API Gateway for a request
+///
+/// This is the `requestContext.identity.sourceIp` of REST APIs and WebSocket APIs, or the
+/// `requestContext.http.sourceIp` of HTTP APIs. Application load balancers don't report a source address.
+///
+/// # Example
+///
+/// ```rust
+/// # use lambda_http::aws_lambda_events::apigw::ApiGatewayV2httpRequestContext;
+/// # use lambda_http::request::RequestContext;
+/// # use lambda_http::Body;
+/// # use std::net::IpAddr;
+/// # use real_ip::lambda::source_ip;
+/// #
+/// let mut context = ApiGatewayV2httpRequestContext::default();
+/// context.http.source_ip = Some("192.0.2.1".into());
+/// let request = http::Request::builder()
+///     .extension(RequestContext::ApiGatewayV2(context))
+///     .body(Body::Empty)
+///     .unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), source_ip(&request));
+/// ```
+pub fn source_ip(request: &Request) -> Option<IpAddr> {
+    context_source_ip(request.request_context_ref()?)
+}
+
+/// The `sourceIp` from the request context in the extensions, as remote address of the connection
+///
+/// This can be used with [`RealIpLayer::with_remote`](crate::RealIpLayer::with_remote) from the `tower` feature when
+/// wrapping the service of the function with the layer.
+pub fn connection_info(extensions: &Extensions) -> Option<ConnectionInfo> {
+    context_source_ip(extensions.get::<RequestContext>()?).map(ConnectionInfo::from)
+}
+
+/// Get the "real-ip" of a request from API Gateway, using the `sourceIp` as remote address
+///
+/// Like [`real_ip`](crate::real_ip), the forwarding headers are only used when the `sourceIp` is one of the
+/// trusted proxies. With a [`RealIpConfig`](crate::RealIpConfig) or [`Resolver`](crate::Resolver), pass the
+/// [`source_ip`] as remote address instead.
+///
+/// # Example
+///
+/// ```rust
+/// # use lambda_http::aws_lambda_events::apigw::ApiGatewayProxyRequestContext;
+/// # use lambda_http::request::RequestContext;
+/// # use lambda_http::Body;
+/// # use std::net::IpAddr;
+/// # use real_ip::lambda::real_ip;
+/// #
+/// // a CloudFront distribution in front of a REST API
+/// let trusted_proxies = ["130.176.0.0/18".parse().unwrap()];
+///
+/// let mut context = ApiGatewayProxyRequestContext::default();
+/// context.identity.source_ip = Some("130.176.1.1".into());
+/// let request = http::Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .extension(RequestContext::ApiGatewayV1(context.clone()))
+///     .body(Body::Empty)
+///     .unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), real_ip(&request, &trusted_proxies));
+///
+/// // a client calling the API directly can't spoof its address
+/// context.identity.source_ip = Some("198.51.100.1".into());
+/// let request = http::Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .extension(RequestContext::ApiGatewayV1(context))
+///     .body(Body::Empty)
+///     .unwrap();
+/// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), real_ip(&request, &trusted_proxies));
+/// ```
+pub fn real_ip(request: &Request, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    crate::real_ip(request, source_ip(request)?, trusted_proxies)
+}
+
+fn context_source_ip(context: &RequestContext) -> Option<IpAddr> {
+    let source_ip = match context {
+        RequestContext::ApiGatewayV1(context) => context.identity.source_ip.as_deref(),
+        RequestContext::ApiGatewayV2(context) => context.http.source_ip.as_deref(),
+        RequestContext::WebSocket(context) => context.identity.source_ip.as_deref(),
+        // application load balancers and custom contexts, depending on the features enabled for lambda_http
+        #[allow(unreachable_patterns)]
+        _ => None,
+    };
+    source_ip?.parse().ok()
+}
@@ -0,0 +1,183 @@
+use crate::{ClientIp, ConnectionInfo, RealIpConfig, RemoteAddr, Resolver};
+#[cfg(all(feature = "axum", not(target_family = "wasm")))]
+use axum::extract::ConnectInfo;
+use http::{Extensions, Request};
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+#[cfg(all(feature = "tonic", not(target_family = "wasm")))]
+use tonic::transport::server::TcpConnectInfo;
+use tower_layer::Layer;
+use tower_service::Service;
+
+type RemoteFn = dyn Fn(&Extensions) -> Option<ConnectionInfo> + Send + Sync;
+
+/// A [`Layer`] that resolves the "real-ip" of every request once and stores it in the request extensions
+///
+/// The [`ClientIp`] is stored for handlers, together with the [`Decision`](crate::Decision) for anything that needs
+/// the full report, see [`Decision::cached`](crate::Decision::cached).
+///
+/// By default the remote address of the connection is taken from a [`ConnectionInfo`], [`RemoteAddr`] or
+/// [`SocketAddr`] extension, from the `ConnectInfo<SocketAddr>` of axum with the `axum` feature or from the
+/// `TcpConnectInfo` of tonic with the `tonic` feature, use [`RealIpLayer::with_remote`] when the server stores it
+/// differently.
+/// Requests without a known remote address are passed on without resolving their address.
+///
+/// Requires the `tower` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::convert::Infallible;
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use tower::{service_fn, Layer, ServiceExt};
+/// # use real_ip::{ClientIp, RealIpConfig, RealIpLayer};
+/// #
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let service = RealIpLayer::new(config).layer(service_fn(|request: Request<()>| async move {
+///     Ok::<_, Infallible>(request.extensions().get::<ClientIp>().copied())
+/// }));
+///
+/// let mut request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// request.extensions_mut().insert(SocketAddr::from(([10, 0, 0, 1], 46532)));
+/// let client_ip = service.oneshot(request).await.unwrap();
+/// assert_eq!(Some(ClientIp(IpAddr::from([192, 0, 2, 1]))), client_ip);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RealIpLayer {
+    resolver: Arc<Resolver>,
+    remote: Arc<RemoteFn>,
+}
+
+impl RealIpLayer {
+    /// Create a layer resolving requests with a [`RealIpConfig`] or [`Resolver`]
+    pub fn new(resolver: impl Into<Resolver>) -> Self {
+        RealIpLayer {
+            resolver: Arc::new(resolver.into()),
+            remote: Arc::new(remote_from_extensions),
+        }
+    }
+
+    /// Take the remote address of the connection from the request extensions with a custom function
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::net::SocketAddr;
+    /// # use real_ip::{RealIpConfig, RealIpLayer};
+    /// #
+    /// // the address stored by a custom server
+    /// #[derive(Clone)]
+    /// struct PeerAddr(SocketAddr);
+    ///
+    /// let layer = RealIpLayer::new(RealIpConfig::default())
+    ///     .with_remote(|extensions| extensions.get::<PeerAddr>().map(|peer| peer.0.into()));
+    /// ```
+    pub fn with_remote(
+        mut self,
+        remote: impl Fn(&Extensions) -> Option<ConnectionInfo> + Send + Sync + 'static,
+    ) -> Self {
+        self.remote = Arc::new(remote);
+        self
+    }
+
+    /// The configuration used by the layer
+    pub fn config(&self) -> &RealIpConfig {
+        self.resolver.config()
+    }
+}
+
+impl Debug for RealIpLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RealIpLayer")
+            .field("resolver", &self.resolver)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Layer<S> for RealIpLayer {
+    type Service = RealIpService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RealIpService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`RealIpLayer`]
+#[derive(Debug, Clone)]
+pub struct RealIpService<S> {
+    inner: S,
+    layer: RealIpLayer,
+}
+
+impl<S, B> Service<Request<B>> for RealIpService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        if let Some(connection) = (self.layer.remote)(request.extensions()) {
+            #[cfg(all(feature = "axum", not(target_family = "wasm")))]
+            request.extensions_mut().insert(LayerContext {
+                resolver: self.layer.resolver.clone(),
+                connection,
+            });
+            let ip = self
+                .layer
+                .resolver
+                .resolve_cached(&mut request, connection)
+                .ip;
+            if let Some(ip) = ip {
+                request.extensions_mut().insert(ClientIp(ip));
+            }
+        }
+        self.inner.call(request)
+    }
+}
+
+/// The resolver and connection of a request passing through the layer, for extractors that resolve differently
+#[cfg(all(feature = "axum", not(target_family = "wasm")))]
+#[derive(Debug, Clone)]
+pub(crate) struct LayerContext {
+    pub(crate) resolver: Arc<Resolver>,
+    pub(crate) connection: ConnectionInfo,
+}
+
+/// Find the remote address in the extensions set by the server
+pub(crate) fn remote_from_extensions(extensions: &Extensions) -> Option<ConnectionInfo> {
+    if let Some(connection) = extensions.get::<ConnectionInfo>() {
+        return Some(*connection);
+    }
+    if let Some(remote) = extensions.get::<RemoteAddr>() {
+        return Some((*remote).into());
+    }
+    #[cfg(all(feature = "axum", not(target_family = "wasm")))]
+    if let Some(ConnectInfo(addr)) = extensions.get::<ConnectInfo<SocketAddr>>() {
+        return Some((*addr).into());
+    }
+    #[cfg(all(feature = "tonic", not(target_family = "wasm")))]
+    if let Some(addr) = extensions
+        .get::<TcpConnectInfo>()
+        .and_then(TcpConnectInfo::remote_addr)
+    {
+        return Some(addr.into());
+    }
+    extensions.get::<SocketAddr>().map(|addr| (*addr).into())
+}
@@ -52,59 +52,510 @@
 //! let client_ip = real_ip(request.headers(), incoming_ip, &trusted_proxies);
 //! assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), client_ip);
 //! ```
+//!
+//! ## `no_std`
+//!
+//! Disabling the default `std` feature makes the crate `no_std`, only requiring `alloc`.
+//! This keeps the header parsing from the [`headers`] module, [`ForwardingHeader`], [`TrustedProxies`]
+//! and the [`TrustPolicy`] evaluation available with [`core::net::IpAddr`].
+//! Resolving the client address of a request requires `std`, as it's built on the `http` crate.
+//!
+//! ## WebAssembly
+//!
+//! The crate supports `wasm32-unknown-unknown` and `wasm32-wasip1`, with and without `std`.
+//! Watching a file for changes with the `notify` feature, resolving proxy hostnames with the `dns` feature and
+//! downloading published ranges with the `fetch` feature and the web framework integrations
+//! aren't available on WebAssembly targets,
+//! the trusted proxies of a `SharedResolver` can still be swapped at runtime with `SharedResolver::update`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "actix-web", not(target_family = "wasm")))]
+pub mod actix;
+#[cfg(feature = "aws-ip-ranges")]
+mod aws;
+#[cfg(feature = "std")]
+mod chain;
+#[cfg(feature = "std")]
+mod client_ip;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod decision;
+#[cfg(all(feature = "dns", not(target_family = "wasm")))]
+mod dns;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod express;
+#[cfg(all(feature = "axum", not(target_family = "wasm")))]
+mod extract;
+#[cfg(all(feature = "fetch", not(target_family = "wasm")))]
+mod fetch;
+mod forwarding_header;
+#[cfg(all(feature = "rocket", not(target_family = "wasm")))]
+mod guard;
 pub mod headers;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(all(feature = "lambda-http", not(target_family = "wasm")))]
+pub mod lambda;
+#[cfg(feature = "tower")]
+mod layer;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+mod load;
+mod network;
+#[cfg(all(feature = "ntex", not(target_family = "wasm")))]
+pub mod ntex;
+#[cfg(all(feature = "poem", not(target_family = "wasm")))]
+pub mod poem;
+mod presets;
+#[cfg(feature = "std")]
+mod provider;
+mod proxies;
+mod remote;
+mod reserved;
+#[cfg(feature = "std")]
+mod resolver;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "arc-swap")]
+mod shared;
+#[cfg(feature = "std")]
+mod source;
+#[cfg(feature = "std")]
+mod store;
+#[cfg(all(feature = "tonic", not(target_family = "wasm")))]
+pub mod tonic;
+mod trie;
+mod trust;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(all(feature = "notify", not(target_family = "wasm")))]
+mod watch;
 
-use crate::headers::{
-    extract_forwarded_header, extract_real_ip_header, extract_x_forwarded_for_header,
+#[cfg(feature = "aws-ip-ranges")]
+pub use crate::aws::AwsIpRanges;
+#[cfg(feature = "std")]
+use crate::chain::Chain;
+#[cfg(feature = "std")]
+pub use crate::client_ip::{ClientIp, ClientIpRejection};
+#[cfg(feature = "std")]
+pub use crate::config::{
+    EmptyElements, Fallback, MalformedElements, OnInvalid, Oversized, RealIpConfig,
+    RealIpConfigBuilder, Strategy, UnknownNodes, ZoneIds,
 };
+#[cfg(feature = "std")]
+pub use crate::decision::{ClientAddr, Decision, ForwardedContext, Hop};
+#[cfg(all(feature = "dns", not(target_family = "wasm")))]
+pub use crate::dns::ProxyHostnameRefresher;
+#[cfg(feature = "std")]
+pub use crate::error::RealIpError;
+#[cfg(feature = "std")]
+pub use crate::express::ParseTrustProxyError;
+#[cfg(all(feature = "axum", not(target_family = "wasm")))]
+pub use crate::extract::{real_ip_make_service, InsecureClientIp, SecureClientIp};
+#[cfg(all(feature = "fetch", not(target_family = "wasm")))]
+pub use crate::fetch::{ProxyRangeRefresher, RangeFeed};
+pub use crate::forwarding_header::{
+    CustomHeader, ForwardingHeader, HeaderFormat, UnknownHeaderError, DEFAULT_HEADERS,
+};
+#[cfg(feature = "tower")]
+pub use crate::layer::{RealIpLayer, RealIpService};
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub use crate::load::ConfigError;
+pub use crate::network::{ipv4_net, ipv6_net, Network};
+#[cfg(feature = "std")]
+pub use crate::provider::{Provider, ProviderProfile, ProviderTrust};
+#[cfg(feature = "std")]
+pub use crate::proxies::TrustedProxiesEnvError;
+pub use crate::proxies::{InvalidTrustedProxies, ParseTrustedProxiesError, TrustedProxies};
+pub use crate::remote::{ConnectionInfo, RemoteAddr};
+#[cfg(feature = "std")]
+pub use crate::resolver::Resolver;
+#[cfg(feature = "arc-swap")]
+pub use crate::shared::SharedResolver;
+#[cfg(feature = "std")]
+pub use crate::source::{RealIpSource, RequestExtensions};
+#[cfg(feature = "std")]
+pub use crate::store::TrustStore;
+pub use crate::trust::{is_trusted_proxy, TrustPolicy};
+#[cfg(feature = "std")]
+pub use crate::validate::ConfigWarning;
+#[cfg(all(feature = "notify", not(target_family = "wasm")))]
+pub use crate::watch::ProxyFileWatcher;
+#[cfg(feature = "std")]
+use core::net::IpAddr;
+#[cfg(feature = "std")]
+use http::request::Parts;
+#[cfg(feature = "std")]
 use http::HeaderMap;
 pub use ipnet::IpNet;
-use itertools::Either;
-use std::iter::{empty, once};
-use std::net::IpAddr;
 
 /// Get the "real-ip" of an incoming request.
 ///
 /// See the [top level documentation](crate) for more usage details.
-pub fn real_ip(headers: &HeaderMap, remote: IpAddr, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
-    let mut hops = get_forwarded_for(headers).chain(once(remote));
-    let first = hops.next();
-    let hops = first.iter().copied().chain(hops);
-
-    'outer: for hop in hops.rev() {
-        for proxy in trusted_proxies {
-            if proxy.contains(&hop) {
-                continue 'outer;
-            }
-        }
-        return Some(hop);
-    }
-
-    // all hops were trusted, return the first one
-    first
+#[cfg(feature = "std")]
+pub fn real_ip(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<IpAddr> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .resolve()
+}
+
+/// Get the "real-ip" of an incoming request from its headers.
+///
+/// This is the same as [`real_ip`], for use alongside [`real_ip_from_parts`] and
+/// [`RealIpConfig::resolve_headers`] when only the headers of the request are available.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::{HeaderMap, HeaderValue};
+/// # use std::net::IpAddr;
+/// # use real_ip::{real_ip_from_headers, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let mut headers = HeaderMap::new();
+/// headers.insert("x-forwarded-for", HeaderValue::from_static("192.0.2.1"));
+///
+/// let client_ip = real_ip_from_headers(&headers, IpAddr::from([10, 0, 0, 1]), &trusted_proxies);
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), client_ip);
+/// ```
+#[cfg(feature = "std")]
+pub fn real_ip_from_headers(
+    headers: &HeaderMap,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<IpAddr> {
+    real_ip(headers, remote, trusted_proxies)
+}
+
+/// Get the "real-ip" of an incoming request that has been split into its parts.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{real_ip_from_parts, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// let (parts, _body) = request.into_parts();
+///
+/// let client_ip = real_ip_from_parts(&parts, IpAddr::from([10, 0, 0, 1]), &trusted_proxies);
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), client_ip);
+/// ```
+#[cfg(feature = "std")]
+pub fn real_ip_from_parts(
+    parts: &Parts,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<IpAddr> {
+    real_ip(&parts.headers, remote, trusted_proxies)
+}
+
+/// Get the "real-ip" of an incoming request, including the client port if it's provided by the forwarding header.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::{real_ip_with_port, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let request = Request::builder().header("forwarded", "for=\"192.0.2.1:46532\"").body(()).unwrap();
+///
+/// let client = real_ip_with_port(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies).unwrap();
+/// assert_eq!(Some(SocketAddr::from(([192, 0, 2, 1], 46532))), client.socket_addr());
+/// ```
+#[cfg(feature = "std")]
+pub fn real_ip_with_port(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<ClientAddr> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .resolve_hop()
+    .map(Hop::client_addr)
+}
+
+/// Get the "real-ip" of an incoming request, using a custom [`TrustPolicy`] to decide which hops are trusted.
+#[cfg(feature = "std")]
+pub fn real_ip_with_policy(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trust_policy: &(impl TrustPolicy + ?Sized),
+) -> Option<IpAddr> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trust_policy,
+    )
+    .resolve()
+}
+
+/// Get the "real-ip" of an incoming request, or the reason it couldn't be determined.
+///
+/// Unlike [`real_ip`] this fails when the forwarding chain looks suspicious, for example when an untrusted
+/// hop claims to forward the request for another client.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{try_real_ip, IpNet, RealIpError};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(
+///     Ok(IpAddr::from([192, 0, 2, 1])),
+///     try_real_ip(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(
+///     Err(RealIpError::UntrustedProxyInChain(IpAddr::from([203, 0, 113, 10]))),
+///     try_real_ip(request.headers(), IpAddr::from([203, 0, 113, 10]), &trusted_proxies)
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn try_real_ip(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Result<IpAddr, RealIpError> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .try_resolve()
+}
+
+/// Get every hop in the forwarding chain of a request, with its trust status.
+///
+/// The hops are ordered from the original client to the remote address of the request.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{hops, ForwardingHeader, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+///
+/// let hops = hops(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies);
+/// assert_eq!(IpAddr::from([192, 0, 2, 1]), hops[0].ip);
+/// assert!(!hops[0].trusted);
+/// assert_eq!(Some(ForwardingHeader::XForwardedFor), hops[0].source_header);
+/// assert_eq!(IpAddr::from([10, 0, 0, 1]), hops[1].ip);
+/// assert!(hops[1].trusted);
+/// assert_eq!(None, hops[1].source_header);
+/// ```
+#[cfg(feature = "std")]
+pub fn hops(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Vec<Hop> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .into_hops()
+}
+
+/// Get the "real-ip" of an incoming request, together with the protocol, host and proxies reported by the forwarding headers.
+///
+/// See [`ForwardedContext`] for an example.
+#[cfg(feature = "std")]
+pub fn forwarded_context(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<ForwardedContext> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .into_context()
+}
+
+/// Get the protocol the client used to connect to the first trusted proxy, e.g. `https`
+///
+/// The protocol is taken from the `proto` parameter of the `forwarded` header or from the `x-forwarded-proto` header,
+/// and is only accepted when it is reported by a trusted proxy, `None` is returned when the client connected directly.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{forwarded_proto, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-proto", "https")
+///     .body(()).unwrap();
+///
+/// assert_eq!(
+///     Some("https".into()),
+///     forwarded_proto(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+/// // the header wasn't set by a trusted proxy
+/// assert_eq!(
+///     None,
+///     forwarded_proto(request.headers(), IpAddr::from([203, 0, 113, 10]), &trusted_proxies)
+/// );
+///
+/// // values prepended by the client are ignored
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-proto", "http, https")
+///     .body(()).unwrap();
+/// assert_eq!(
+///     Some("https".into()),
+///     forwarded_proto(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+///
+/// // the proxy overwrote the protocol, but appended to the addresses sent by the client
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "198.51.100.7, 192.0.2.1")
+///     .header("x-forwarded-proto", "https")
+///     .body(()).unwrap();
+/// assert_eq!(
+///     Some("https".into()),
+///     forwarded_proto(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn forwarded_proto(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<String> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .resolve_hop()
+    .and_then(|hop| hop.proto.clone())
+}
+
+/// Get the host the client requested from the first trusted proxy, including the port if one was requested
+///
+/// The host is taken from the `host` parameter of the `forwarded` header or from the `x-forwarded-host` header,
+/// and is only accepted when it is reported by a trusted proxy, `None` is returned when the client connected directly.
+/// Use the `host` header of the request in that case.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{forwarded_host, IpNet};
+/// #
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-host", "Example.com")
+///     .body(()).unwrap();
+///
+/// assert_eq!(
+///     Some("example.com".into()),
+///     forwarded_host(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+/// // the header wasn't set by a trusted proxy
+/// assert_eq!(
+///     None,
+///     forwarded_host(request.headers(), IpAddr::from([203, 0, 113, 10]), &trusted_proxies)
+/// );
+///
+/// // a host prepended by the client is ignored, the proxy appended its own
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1")
+///     .header("x-forwarded-host", "evil.example, good.example")
+///     .body(()).unwrap();
+/// assert_eq!(
+///     Some("good.example".into()),
+///     forwarded_host(request.headers(), IpAddr::from([10, 0, 0, 1]), &trusted_proxies)
+/// );
+/// ```
+#[cfg(feature = "std")]
+pub fn forwarded_host(
+    request: &impl RealIpSource,
+    remote: impl Into<ConnectionInfo>,
+    trusted_proxies: &[IpNet],
+) -> Option<String> {
+    Chain::new(
+        &RealIpConfig::default(),
+        request,
+        remote.into(),
+        trusted_proxies,
+    )
+    .resolve_hop()
+    .and_then(|hop| hop.host.clone())
 }
 
 /// Extracts the ip addresses from the "forwarded for" chain from a request
 ///
 /// Note that this doesn't perform any validation against clients forging the headers
+///
+/// If the header is present multiple times, the addresses from all instances are returned in order.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::get_forwarded_for;
+/// #
+/// let request = Request::builder()
+///     .header("x-forwarded-for", "192.0.2.1, 10.10.10.10")
+///     .header("x-forwarded-for", "10.0.0.1")
+///     .body(()).unwrap();
+/// assert_eq!(
+///     vec![IpAddr::from([192, 0, 2, 1]), IpAddr::from([10, 10, 10, 10]), IpAddr::from([10, 0, 0, 1])],
+///     get_forwarded_for(request.headers()).collect::<Vec<_>>()
+/// );
+/// ```
+#[cfg(feature = "std")]
 pub fn get_forwarded_for(headers: &HeaderMap) -> impl DoubleEndedIterator<Item = IpAddr> + '_ {
-    if let Some(header) = headers.get("forwarded") {
-        let header = header.to_str().unwrap_or_default();
-        return Either::Left(Either::Left(extract_forwarded_header(header)));
-    }
-
-    if let Some(header) = headers.get("x-forwarded-for") {
-        let header = header.to_str().unwrap_or_default();
-        return Either::Left(Either::Right(extract_x_forwarded_for_header(header)));
-    }
-
-    if let Some(header) = headers.get("x-real-ip") {
-        let header = header.to_str().unwrap_or_default();
-        return Either::Right(Either::Left(extract_real_ip_header(header)));
-    }
-
-    Either::Right(Either::Right(empty()))
+    DEFAULT_HEADERS
+        .iter()
+        .find(|header| headers.contains_key(header.name()))
+        .into_iter()
+        .flat_map(move |header| header.extract_all(headers))
 }
 
 #[allow(dead_code)]
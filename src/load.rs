@@ -0,0 +1,107 @@
+use crate::RealIpConfig;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+impl RealIpConfig {
+    /// Load the configuration from a TOML document.
+    ///
+    /// The document uses the same format as the `serde` implementation of [`RealIpConfig`],
+    /// unknown settings are rejected so typos don't silently fall back to the default.
+    ///
+    /// Requires the `toml` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::RealIpConfig;
+    /// #
+    /// let config = RealIpConfig::from_toml_str(r#"
+    ///     trusted_proxies = ["10.0.0.0/8", "203.0.113.10"]
+    ///     headers = ["forwarded", "x-forwarded-for"]
+    ///     strategy = "rightmost_untrusted"
+    /// "#)?;
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let err = RealIpConfig::from_toml_str(r#"trusted_proxy = ["10.0.0.0/8"]"#).unwrap_err();
+    /// assert!(err.to_string().contains("unknown field `trusted_proxy`"));
+    /// # Ok::<(), real_ip::ConfigError>(())
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(document: &str) -> Result<Self, ConfigError> {
+        toml::from_str(document).map_err(ConfigError::Toml)
+    }
+
+    /// Load the configuration from a YAML document.
+    ///
+    /// The document uses the same format as the `serde` implementation of [`RealIpConfig`],
+    /// unknown settings are rejected so typos don't silently fall back to the default.
+    /// Settings with a value, like [`Strategy::TrustedHopCount`](crate::Strategy::TrustedHopCount), are written as a tag.
+    ///
+    /// Requires the `yaml` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::RealIpConfig;
+    /// #
+    /// let config = RealIpConfig::from_yaml_str(r#"
+    /// trusted_proxies:
+    ///   - 10.0.0.0/8
+    ///   - 203.0.113.10
+    /// headers: [x-forwarded-for]
+    /// strategy: !trusted_hop_count 2
+    /// "#)?;
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+    ///
+    /// let err = RealIpConfig::from_yaml_str("trusted_proxies: [10.0.0.0/33]").unwrap_err();
+    /// assert!(err.to_string().contains("invalid trusted proxy 10.0.0.0/33"));
+    /// # Ok::<(), real_ip::ConfigError>(())
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(document: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(document).map_err(ConfigError::Yaml)
+    }
+}
+
+/// Error returned when loading the configuration from a document
+///
+/// The error describes the setting that is invalid and, where possible, its position in the document.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The TOML document is invalid
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The YAML document is invalid
+    #[cfg(feature = "yaml")]
+    Yaml(serde_yaml::Error),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "toml")]
+            ConfigError::Toml(err) => write!(f, "invalid real-ip configuration: {}", err),
+            #[cfg(feature = "yaml")]
+            ConfigError::Yaml(err) => write!(f, "invalid real-ip configuration: {}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "toml")]
+            ConfigError::Toml(err) => Some(err),
+            #[cfg(feature = "yaml")]
+            ConfigError::Yaml(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+/// A network that can be used as a trusted proxy
+///
+/// This is implemented for the network types of the `ipnet` crate and for single addresses.
+/// With the `ipnetwork` feature it's also implemented for the network types of the `ipnetwork` crate.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::{IpNet, TrustedProxies};
+/// #
+/// let proxies: TrustedProxies = [IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2])].into_iter().collect();
+/// assert!(proxies.contains(IpAddr::from([10, 0, 0, 2])));
+/// ```
+///
+/// Networks from the `ipnetwork` crate can be collected the same way.
+///
+/// ```rust
+/// # #[cfg(feature = "ipnetwork")]
+/// # {
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use ipnetwork::IpNetwork;
+/// # use real_ip::{RealIpConfig, TrustedProxies};
+/// #
+/// let networks: Vec<IpNetwork> = vec!["10.0.0.0/8".parse().unwrap()];
+/// let proxies: TrustedProxies = networks.iter().collect();
+/// let config = RealIpConfig::builder().trusted_proxies(proxies).build();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+/// # }
+/// ```
+pub trait Network {
+    /// The network as [`IpNet`]
+    fn to_ipnet(&self) -> IpNet;
+}
+
+/// Create an IPv4 network, usable in `const` and `static` items.
+///
+/// Panics if the prefix length is larger than 32, which fails compilation when used in a constant.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{ipv4_net, ipv6_net, real_ip, IpNet};
+/// #
+/// static TRUSTED_PROXIES: [IpNet; 2] = [
+///     ipv4_net([10, 0, 0, 0], 8),
+///     ipv6_net([0xfd00, 0, 0, 0, 0, 0, 0, 0], 8),
+/// ];
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), real_ip(&request, IpAddr::from([10, 0, 0, 1]), &TRUSTED_PROXIES));
+/// ```
+pub const fn ipv4_net(addr: [u8; 4], prefix_len: u8) -> IpNet {
+    let [a, b, c, d] = addr;
+    IpNet::V4(Ipv4Net::new_assert(Ipv4Addr::new(a, b, c, d), prefix_len))
+}
+
+/// Create an IPv6 network from its segments, usable in `const` and `static` items.
+///
+/// Panics if the prefix length is larger than 128, which fails compilation when used in a constant.
+pub const fn ipv6_net(segments: [u16; 8], prefix_len: u8) -> IpNet {
+    let [a, b, c, d, e, f, g, h] = segments;
+    IpNet::V6(Ipv6Net::new_assert(
+        Ipv6Addr::new(a, b, c, d, e, f, g, h),
+        prefix_len,
+    ))
+}
+
+impl Network for IpNet {
+    fn to_ipnet(&self) -> IpNet {
+        *self
+    }
+}
+
+impl Network for Ipv4Net {
+    fn to_ipnet(&self) -> IpNet {
+        (*self).into()
+    }
+}
+
+impl Network for Ipv6Net {
+    fn to_ipnet(&self) -> IpNet {
+        (*self).into()
+    }
+}
+
+impl Network for IpAddr {
+    fn to_ipnet(&self) -> IpNet {
+        (*self).into()
+    }
+}
+
+impl Network for Ipv4Addr {
+    fn to_ipnet(&self) -> IpNet {
+        IpAddr::from(*self).into()
+    }
+}
+
+impl Network for Ipv6Addr {
+    fn to_ipnet(&self) -> IpNet {
+        IpAddr::from(*self).into()
+    }
+}
+
+impl<N: Network + ?Sized> Network for &N {
+    fn to_ipnet(&self) -> IpNet {
+        (**self).to_ipnet()
+    }
+}
+
+#[cfg(feature = "ipnetwork")]
+mod ipnetwork_impls {
+    use super::Network;
+    use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+    use ipnetwork::{IpNetwork, Ipv4Network, Ipv6Network};
+
+    impl Network for IpNetwork {
+        fn to_ipnet(&self) -> IpNet {
+            match self {
+                IpNetwork::V4(network) => network.to_ipnet(),
+                IpNetwork::V6(network) => network.to_ipnet(),
+            }
+        }
+    }
+
+    impl Network for Ipv4Network {
+        fn to_ipnet(&self) -> IpNet {
+            Ipv4Net::new_assert(self.ip(), self.prefix()).into()
+        }
+    }
+
+    impl Network for Ipv6Network {
+        fn to_ipnet(&self) -> IpNet {
+            Ipv6Net::new_assert(self.ip(), self.prefix()).into()
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Middleware and extractor for [ntex](https://ntex.rs)
+//!
+//! Requires the `ntex` feature.
+
+use crate::{ClientIp, ClientIpRejection, Decision, Resolver};
+use ntex::http::header::HeaderMap;
+use ntex::http::{Payload, StatusCode};
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{
+    DefaultError, ErrorRenderer, FromRequest, HttpRequest, WebRequest, WebResponse,
+    WebResponseError,
+};
+use std::rc::Rc;
+
+/// Middleware that resolves the "real-ip" of every request once and stores it in the request extensions
+///
+/// The [`ClientIp`] is stored for handlers, together with the [`Decision`] for anything that needs the full report.
+/// The remote address is taken from the peer address of the connection, requests without peer address are passed
+/// on without resolving their address.
+///
+/// # Example
+///
+/// ```rust
+/// # use ntex::web::{self, test, App};
+/// # use std::net::IpAddr;
+/// # use real_ip::ntex::RealIpMiddleware;
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// # #[ntex::main]
+/// # async fn main() {
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([127, 0, 0, 1]).into())
+///     .build();
+/// let server = test::server(move || {
+///     App::new()
+///         .wrap(RealIpMiddleware::new(config.clone()))
+///         .route("/", web::get().to(|ClientIp(ip): ClientIp| async move { ip.to_string() }))
+/// });
+///
+/// let mut response = server
+///     .get("/")
+///     .header("x-forwarded-for", "198.51.100.1, 192.0.2.1")
+///     .send()
+///     .await
+///     .unwrap();
+/// assert_eq!("192.0.2.1", response.body().await.unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RealIpMiddleware {
+    resolver: Rc<Resolver>,
+}
+
+impl RealIpMiddleware {
+    /// Create the middleware resolving requests with a [`RealIpConfig`](crate::RealIpConfig) or [`Resolver`]
+    pub fn new(resolver: impl Into<Resolver>) -> Self {
+        RealIpMiddleware {
+            resolver: Rc::new(resolver.into()),
+        }
+    }
+}
+
+impl<S> Middleware<S> for RealIpMiddleware {
+    type Service = RealIpMiddlewareService<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        RealIpMiddlewareService {
+            service,
+            resolver: self.resolver.clone(),
+        }
+    }
+}
+
+/// The service created by [`RealIpMiddleware`]
+#[derive(Debug)]
+pub struct RealIpMiddlewareService<S> {
+    service: S,
+    resolver: Rc<Resolver>,
+}
+
+impl<S, Err> Service<WebRequest<Err>> for RealIpMiddlewareService<S>
+where
+    S: Service<WebRequest<Err>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_poll!(service);
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        request: WebRequest<Err>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Some(remote) = request.peer_addr() {
+            let headers = to_http_headers(request.headers());
+            let decision = self.resolver.resolve_with_report(&headers, remote);
+            if let Some(ip) = decision.ip {
+                request.extensions_mut().insert(ClientIp(ip));
+            }
+            request.extensions_mut().insert(decision);
+        }
+        ctx.call(&self.service, request).await
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for ClientIp {
+    type Error = ClientIpRejection;
+
+    async fn from_request(
+        request: &HttpRequest,
+        _payload: &mut Payload,
+    ) -> Result<Self, Self::Error> {
+        let extensions = request.extensions();
+        match (extensions.get::<ClientIp>(), extensions.get::<Decision>()) {
+            (Some(ip), _) => Ok(*ip),
+            (None, Some(decision)) => Err(ClientIpRejection::Unresolved(decision.error.clone())),
+            (None, None) if request.peer_addr().is_none() => {
+                Err(ClientIpRejection::MissingConnectInfo)
+            }
+            (None, None) => Err(ClientIpRejection::MissingConfig),
+        }
+    }
+}
+
+impl WebResponseError<DefaultError> for ClientIpRejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status().as_u16()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// Copy the headers of an ntex request, which uses its own `http` types
+fn to_http_headers(headers: &HeaderMap) -> http::HeaderMap {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                http::HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+                http::HeaderValue::from_bytes(value.as_bytes()).ok()?,
+            ))
+        })
+        .collect()
+}
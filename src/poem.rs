@@ -0,0 +1,124 @@
+//! Middleware and extractor for [poem](https://github.com/poem-web/poem)
+//!
+//! Requires the `poem` feature.
+
+use crate::{ClientIp, ClientIpRejection, ConnectionInfo, Decision, RemoteAddr, Resolver};
+use http::StatusCode;
+use poem::error::ResponseError;
+use poem::{Addr, Endpoint, FromRequest, Middleware, Request, RequestBody};
+use std::sync::Arc;
+
+/// Middleware that resolves the "real-ip" of every request once and stores it in the request extensions
+///
+/// The [`ClientIp`] is stored for handlers, together with the [`Decision`] for anything that needs the full report.
+/// The remote address is taken from the `RemoteAddr` of the request, requests from a unix socket are handled as
+/// described for [`RemoteAddr::unix_socket`].
+///
+/// # Example
+///
+/// ```rust
+/// # use poem::web::{LocalAddr, RemoteAddr};
+/// # use poem::{handler, Addr, Body, Endpoint, EndpointExt, Request, RequestParts, Route};
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::poem::RealIpMiddleware;
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// #[handler]
+/// fn index(ClientIp(ip): ClientIp) -> String {
+///     ip.to_string()
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let app = Route::new().at("/", index).with(RealIpMiddleware::new(config));
+///
+/// let (parts, _) = http::Request::builder().uri("/").header("x-forwarded-for", "192.0.2.1").body(()).unwrap().into_parts();
+/// let remote = RemoteAddr(Addr::SocketAddr(SocketAddr::from(([10, 0, 0, 1], 46532))));
+/// let parts = RequestParts::from((parts, LocalAddr::default(), remote, http::uri::Scheme::HTTP));
+/// let response = app.call(Request::from_parts(parts, Body::empty())).await.unwrap();
+/// assert_eq!("192.0.2.1", response.into_body().into_string().await.unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RealIpMiddleware {
+    resolver: Arc<Resolver>,
+}
+
+impl RealIpMiddleware {
+    /// Create the middleware resolving requests with a [`RealIpConfig`](crate::RealIpConfig) or [`Resolver`]
+    pub fn new(resolver: impl Into<Resolver>) -> Self {
+        RealIpMiddleware {
+            resolver: Arc::new(resolver.into()),
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RealIpMiddleware {
+    type Output = RealIpEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        RealIpEndpoint {
+            inner,
+            resolver: self.resolver.clone(),
+        }
+    }
+}
+
+/// The endpoint created by [`RealIpMiddleware`]
+#[derive(Debug)]
+pub struct RealIpEndpoint<E> {
+    inner: E,
+    resolver: Arc<Resolver>,
+}
+
+impl<E: Endpoint> Endpoint for RealIpEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut request: Request) -> poem::Result<Self::Output> {
+        if let Some(connection) = connection_info(request.remote_addr()) {
+            let decision = self
+                .resolver
+                .resolve_with_report(request.headers(), connection);
+            if let Some(ip) = decision.ip {
+                request.extensions_mut().insert(ClientIp(ip));
+            }
+            request.extensions_mut().insert(decision);
+        }
+        self.inner.call(request).await
+    }
+}
+
+impl<'a> FromRequest<'a> for ClientIp {
+    async fn from_request(request: &'a Request, _body: &mut RequestBody) -> poem::Result<Self> {
+        let extensions = request.extensions();
+        match (extensions.get::<ClientIp>(), extensions.get::<Decision>()) {
+            (Some(ip), _) => Ok(*ip),
+            (None, Some(decision)) => {
+                Err(ClientIpRejection::Unresolved(decision.error.clone()).into())
+            }
+            (None, None) if connection_info(request.remote_addr()).is_none() => {
+                Err(ClientIpRejection::MissingConnectInfo.into())
+            }
+            (None, None) => Err(ClientIpRejection::MissingConfig.into()),
+        }
+    }
+}
+
+impl ResponseError for ClientIpRejection {
+    fn status(&self) -> StatusCode {
+        ClientIpRejection::status(self)
+    }
+}
+
+/// The connection of a request, from the remote address provided by poem
+fn connection_info(remote: &poem::web::RemoteAddr) -> Option<ConnectionInfo> {
+    match &remote.0 {
+        Addr::SocketAddr(addr) => Some((*addr).into()),
+        #[cfg(unix)]
+        Addr::Unix(_) => Some(RemoteAddr::unix_socket().into()),
+        _ => None,
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(any(
+    feature = "preset-azure",
+    feature = "preset-cloudflare",
+    feature = "preset-fastly",
+    feature = "preset-google-cloud"
+))]
+use crate::reserved::Networks;
+
+/// The ranges of Cloudflare's edge servers, from <https://www.cloudflare.com/ips/>
+#[cfg(feature = "preset-cloudflare")]
+pub(crate) const CLOUDFLARE: Networks = Networks {
+    v4: &[
+        ([173, 245, 48, 0], 20),
+        ([103, 21, 244, 0], 22),
+        ([103, 22, 200, 0], 22),
+        ([103, 31, 4, 0], 22),
+        ([141, 101, 64, 0], 18),
+        ([108, 162, 192, 0], 18),
+        ([190, 93, 240, 0], 20),
+        ([188, 114, 96, 0], 20),
+        ([197, 234, 240, 0], 22),
+        ([198, 41, 128, 0], 17),
+        ([162, 158, 0, 0], 15),
+        ([104, 16, 0, 0], 13),
+        ([104, 24, 0, 0], 14),
+        ([172, 64, 0, 0], 13),
+        ([131, 0, 72, 0], 22),
+    ],
+    v6: &[
+        ([0x2400, 0xcb00, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2606, 0x4700, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2803, 0xf800, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2405, 0xb500, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2405, 0x8100, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2a06, 0x98c0, 0, 0, 0, 0, 0, 0], 29),
+        ([0x2c0f, 0xf248, 0, 0, 0, 0, 0, 0], 32),
+    ],
+};
+
+/// The ranges of Fastly's edge servers, from <https://api.fastly.com/public-ip-list>
+#[cfg(feature = "preset-fastly")]
+pub(crate) const FASTLY: Networks = Networks {
+    v4: &[
+        ([23, 235, 32, 0], 20),
+        ([43, 249, 72, 0], 22),
+        ([103, 244, 50, 0], 24),
+        ([103, 245, 222, 0], 23),
+        ([103, 245, 224, 0], 24),
+        ([104, 156, 80, 0], 20),
+        ([140, 248, 64, 0], 18),
+        ([140, 248, 128, 0], 17),
+        ([146, 75, 0, 0], 17),
+        ([151, 101, 0, 0], 16),
+        ([157, 52, 64, 0], 18),
+        ([167, 82, 0, 0], 17),
+        ([167, 82, 128, 0], 20),
+        ([167, 82, 160, 0], 20),
+        ([167, 82, 224, 0], 20),
+        ([172, 111, 64, 0], 18),
+        ([185, 31, 16, 0], 22),
+        ([199, 27, 72, 0], 21),
+        ([199, 232, 0, 0], 16),
+    ],
+    v6: &[
+        ([0x2a04, 0x4e40, 0, 0, 0, 0, 0, 0], 32),
+        ([0x2a04, 0x4e42, 0, 0, 0, 0, 0, 0], 32),
+    ],
+};
+
+/// The ranges Google Cloud load balancers and Cloud CDN connect to backends from,
+/// from <https://cloud.google.com/load-balancing/docs/health-checks#firewall_rules>
+#[cfg(feature = "preset-google-cloud")]
+pub(crate) const GOOGLE_CLOUD_LOAD_BALANCER: Networks = Networks {
+    v4: &[([35, 191, 0, 0], 16), ([130, 211, 0, 0], 22)],
+    v6: &[],
+};
+
+/// The ranges Azure Front Door connects to backends from, the `AzureFrontDoor.Backend` service tag,
+/// from <https://learn.microsoft.com/en-us/azure/frontdoor/origin-security>
+#[cfg(feature = "preset-azure")]
+pub(crate) const AZURE_FRONT_DOOR: Networks = Networks {
+    v4: &[([147, 243, 0, 0], 16)],
+    v6: &[([0x2a01, 0x111, 0x2050, 0, 0, 0, 0, 0], 44)],
+};
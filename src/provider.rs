@@ -0,0 +1,314 @@
+use crate::{ForwardingHeader, RealIpConfigBuilder, Strategy, TrustedProxies};
+
+/// A CDN or hosting provider in front of the application, see [`RealIpConfigBuilder::provider`]
+///
+/// Every provider is described by a [`ProviderProfile`], which can be adjusted before it is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Provider {
+    /// Akamai, reading the `true-client-ip` header set by its edge servers
+    ///
+    /// Akamai doesn't publish the ranges of its edge servers, the servers that connect to the origin are listed in
+    /// the Site Shield map of the property, which has to be provided.
+    Akamai {
+        /// The ranges of the Site Shield map
+        site_shield: TrustedProxies,
+    },
+    /// Fly.io, reading the `fly-client-ip` header set by the Fly proxy
+    ///
+    /// Fly doesn't publish the addresses of its proxies, instead the single hop in front of the application is trusted.
+    /// This is only safe when the application can't be reached without going through the Fly proxy.
+    Fly,
+    /// Heroku, reading the `x-forwarded-for` header the Heroku router appends the client address to
+    ///
+    /// Heroku doesn't publish the addresses of its routers, instead the single hop in front of the application
+    /// is trusted, which is always a router.
+    Heroku,
+    /// Vercel, reading the `x-vercel-forwarded-for` or `x-real-ip` headers set by the Vercel proxy
+    ///
+    /// The single hop in front of the application is trusted, which is always the Vercel proxy.
+    Vercel,
+    /// Render, reading the `true-client-ip` header set by Render's edge, or the `x-forwarded-for` header
+    /// the Render load balancer appends the client address to
+    ///
+    /// Render doesn't publish the addresses of its load balancers, instead the single hop in front of the
+    /// application is trusted, which is always a load balancer.
+    Render,
+    /// Google App Engine, reading the `x-appengine-user-ip` header set by the App Engine frontend
+    ///
+    /// The frontend connects to the application from a link-local address, which can't be used
+    /// by clients connecting from outside, so only requests from link-local addresses are trusted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Provider, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder().provider(Provider::AppEngine).build();
+    ///
+    /// let request = Request::builder().header("x-appengine-user-ip", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([169, 254, 1, 1])));
+    /// assert_eq!(Some(IpAddr::from([198, 51, 100, 1])), config.resolve(&request, IpAddr::from([198, 51, 100, 1])));
+    /// ```
+    AppEngine,
+    /// Cloudflare, reading the `cf-connecting-ip` or `x-forwarded-for` headers from the ranges of
+    /// [`TrustedProxies::cloudflare`]
+    ///
+    /// Requires the `preset-cloudflare` feature.
+    #[cfg(feature = "preset-cloudflare")]
+    Cloudflare,
+    /// Fastly, reading the `x-forwarded-for` header from the ranges of [`TrustedProxies::fastly`]
+    ///
+    /// Services that overwrite `fastly-client-ip` in VCL can read that header instead by building a
+    /// [`ProviderProfile`] with [`ForwardingHeader::FastlyClientIp`].
+    ///
+    /// Requires the `preset-fastly` feature.
+    #[cfg(feature = "preset-fastly")]
+    Fastly,
+    /// Google Cloud load balancers, reading the `x-forwarded-for` header from the ranges of
+    /// [`TrustedProxies::google_cloud_load_balancer`]
+    ///
+    /// The load balancer appends both the client address and its own address to the header,
+    /// so the address of the load balancer is skipped.
+    ///
+    /// Requires the `preset-google-cloud` feature.
+    #[cfg(feature = "preset-google-cloud")]
+    GoogleCloud,
+    /// Azure Front Door, reading the `x-azure-socketip` or `x-forwarded-for` headers from the ranges of
+    /// [`TrustedProxies::azure_front_door`]
+    ///
+    /// The `x-azure-clientip` header isn't used by default, since Front Door fills it from an `x-forwarded-for`
+    /// header sent by the client.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Provider, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder().provider(Provider::AzureFrontDoor).build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("x-azure-clientip", "198.51.100.1")
+    ///     .header("x-azure-socketip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1, 192.0.2.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([147, 243, 1, 1])));
+    /// ```
+    ///
+    /// Requires the `preset-azure` feature.
+    #[cfg(feature = "preset-azure")]
+    AzureFrontDoor,
+    /// AWS CloudFront, reading the `cloudfront-viewer-address` or `x-forwarded-for` headers from the
+    /// `CLOUDFRONT_ORIGIN_FACING` ranges, which can be loaded with `AwsIpRanges` from the `aws-ip-ranges` feature
+    ///
+    /// CloudFront only sends the `cloudfront-viewer-address` header, which includes the port of the client,
+    /// when it is added to the origin request policy.
+    ///
+    /// Unlike the other providers there is no compiled-in preset for the ranges, AWS changes them too often for a
+    /// list that is only updated with new releases. `RangeFeed::Aws` from the `fetch` feature keeps them current.
+    CloudFront {
+        /// The ranges CloudFront connects to origins from
+        origin_facing: TrustedProxies,
+    },
+}
+
+impl Provider {
+    /// The headers, trust and quirks of the provider
+    pub fn profile(self) -> ProviderProfile {
+        use ForwardingHeader::*;
+
+        match self {
+            Provider::Akamai { site_shield } => {
+                ProviderProfile::new([TrueClientIp], ProviderTrust::Proxies(site_shield))
+            }
+            Provider::Fly => ProviderProfile::new([FlyClientIp], ProviderTrust::HopCount(1)),
+            Provider::Heroku => ProviderProfile::new([XForwardedFor], ProviderTrust::HopCount(1)),
+            Provider::Vercel => {
+                ProviderProfile::new([XVercelForwardedFor, XRealIp], ProviderTrust::HopCount(1))
+            }
+            Provider::Render => {
+                ProviderProfile::new([TrueClientIp, XForwardedFor], ProviderTrust::HopCount(1))
+            }
+            Provider::AppEngine => ProviderProfile::new(
+                [XAppengineUserIp],
+                ProviderTrust::Proxies(TrustedProxies::link_local()),
+            ),
+            #[cfg(feature = "preset-cloudflare")]
+            Provider::Cloudflare => ProviderProfile::new(
+                [CfConnectingIp, XForwardedFor],
+                ProviderTrust::Proxies(TrustedProxies::cloudflare()),
+            ),
+            #[cfg(feature = "preset-fastly")]
+            Provider::Fastly => ProviderProfile::new(
+                [XForwardedFor],
+                ProviderTrust::Proxies(TrustedProxies::fastly()),
+            ),
+            #[cfg(feature = "preset-google-cloud")]
+            Provider::GoogleCloud => ProviderProfile {
+                appended_hops: 1,
+                ..ProviderProfile::new(
+                    [XForwardedFor],
+                    ProviderTrust::Proxies(TrustedProxies::google_cloud_load_balancer()),
+                )
+            },
+            #[cfg(feature = "preset-azure")]
+            Provider::AzureFrontDoor => ProviderProfile::new(
+                [XAzureSocketIp, XForwardedFor],
+                ProviderTrust::Proxies(TrustedProxies::azure_front_door()),
+            ),
+            Provider::CloudFront { origin_facing } => ProviderProfile::new(
+                [CloudFrontViewerAddress, XForwardedFor],
+                ProviderTrust::Proxies(origin_facing),
+            ),
+        }
+    }
+}
+
+impl From<Provider> for ProviderProfile {
+    fn from(provider: Provider) -> Self {
+        provider.profile()
+    }
+}
+
+/// How the proxies of a provider are recognized
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProviderTrust {
+    /// Trust the hops within the ranges of the provider
+    Proxies(TrustedProxies),
+    /// Trust a fixed number of hops from the right of the chain, for providers that don't publish their ranges,
+    /// see [`Strategy::TrustedHopCount`]
+    HopCount(usize),
+}
+
+/// The headers, trust and quirks of a CDN or hosting provider, see [`RealIpConfigBuilder::provider`]
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{ForwardingHeader, ProviderProfile, ProviderTrust, RealIpConfig};
+/// #
+/// // a load balancer that appends the client address and its own address to x-forwarded-for
+/// let mut profile = ProviderProfile::new(
+///     [ForwardingHeader::XForwardedFor],
+///     ProviderTrust::Proxies("203.0.113.0/24".parse().unwrap()),
+/// );
+/// profile.appended_hops = 1;
+/// let config = RealIpConfig::builder().provider(profile).build();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 198.51.100.10").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([203, 0, 113, 5])));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProviderProfile {
+    /// The headers set by the provider, in order of preference
+    pub headers: Vec<ForwardingHeader>,
+    /// How the proxies of the provider are recognized
+    pub trust: ProviderTrust,
+    /// The number of hops a trusted proxy appends to the header after the client address,
+    /// like the address of a load balancer, which are trusted regardless of their address
+    pub appended_hops: usize,
+}
+
+impl ProviderProfile {
+    /// A profile reading the provided headers
+    pub fn new(headers: impl IntoIterator<Item = ForwardingHeader>, trust: ProviderTrust) -> Self {
+        ProviderProfile {
+            headers: headers.into_iter().collect(),
+            trust,
+            appended_hops: 0,
+        }
+    }
+}
+
+impl RealIpConfigBuilder {
+    /// Configure the headers and trusted proxies for a CDN or hosting provider
+    ///
+    /// This replaces the configured headers and strategy and adds the ranges of the provider to the trusted proxies,
+    /// other options can still be changed afterwards.
+    /// When the profile reads the `cf-connecting-ip` header, the ranges are also used as the
+    /// [Cloudflare ranges](Self::cloudflare_proxies) that the header is accepted from.
+    /// Profiles with appended hops check the trusted proxies with a custom [`TrustPolicy`](crate::TrustPolicy),
+    /// so further calls to [`trusted_proxy`](Self::trusted_proxy) have no effect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Provider, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .provider(Provider::Akamai {
+    ///         site_shield: "23.48.168.0/22, 2.16.0.0/13".parse().unwrap(),
+    ///     })
+    ///     .build();
+    ///
+    /// let request = Request::builder()
+    ///     .header("true-client-ip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([23, 48, 168, 10])));
+    ///
+    /// // not from an Akamai server
+    /// assert_eq!(Some(IpAddr::from([203, 0, 113, 5])), config.resolve(&request, IpAddr::from([203, 0, 113, 5])));
+    ///
+    /// let config = RealIpConfig::builder().provider(Provider::Fly).build();
+    /// let request = Request::builder()
+    ///     .header("fly-client-ip", "192.0.2.1")
+    ///     .header("x-forwarded-for", "198.51.100.1, 192.0.2.1, 66.241.124.10")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([172, 16, 3, 2])));
+    ///
+    /// // the client tries to spoof its address, the router appends the actual address
+    /// let config = RealIpConfig::builder().provider(Provider::Heroku).build();
+    /// let request = Request::builder().header("x-forwarded-for", "198.51.100.1, 192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 1, 20, 30])));
+    ///
+    /// let config = RealIpConfig::builder().provider(Provider::Vercel).build();
+    /// let request = Request::builder().header("x-vercel-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 1, 20, 30])));
+    ///
+    /// let config = RealIpConfig::builder().provider(Provider::Render).build();
+    /// let request = Request::builder().header("x-forwarded-for", "198.51.100.1, 192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([10, 1, 20, 30])));
+    /// ```
+    pub fn provider(self, provider: impl Into<ProviderProfile>) -> Self {
+        let profile = provider.into();
+        let mut builder = self.headers(profile.headers.iter().cloned());
+        if let ProviderTrust::Proxies(proxies) = &profile.trust {
+            if profile.headers.contains(&ForwardingHeader::CfConnectingIp) {
+                builder = builder.cloudflare_proxies(proxies.networks().iter().copied());
+            }
+        }
+        let appended = profile.appended_hops;
+        match profile.trust {
+            ProviderTrust::HopCount(count) => {
+                builder.strategy(Strategy::TrustedHopCount(count + appended))
+            }
+            ProviderTrust::Proxies(proxies) if appended == 0 => builder
+                .strategy(Strategy::RightmostUntrusted)
+                .trusted_proxies(proxies),
+            ProviderTrust::Proxies(proxies) => {
+                // the appended hops are only used when the proxy to their right is trusted,
+                // since the chain ends at the right-most untrusted hop
+                builder
+                    .strategy(Strategy::RightmostUntrusted)
+                    .trust_policy(move |ip, index| match index {
+                        0 => proxies.contains(ip),
+                        index => index <= appended,
+                    })
+            }
+        }
+    }
+}
@@ -0,0 +1,530 @@
+#[cfg(feature = "preset-azure")]
+use crate::presets::AZURE_FRONT_DOOR;
+#[cfg(feature = "preset-cloudflare")]
+use crate::presets::CLOUDFLARE;
+#[cfg(feature = "preset-fastly")]
+use crate::presets::FASTLY;
+#[cfg(feature = "preset-google-cloud")]
+use crate::presets::GOOGLE_CLOUD_LOAD_BALANCER;
+use crate::reserved::{Networks, CARRIER_GRADE_NAT, LINK_LOCAL, LOOPBACK, PRIVATE};
+use crate::trie::PrefixTrie;
+use crate::{Network, TrustPolicy};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+use core::net::IpAddr;
+use core::str::FromStr;
+use ipnet::IpNet;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::{env, fs, io};
+
+/// A list of trusted proxy networks
+///
+/// The networks are stored in a prefix tree, so checking if an address is trusted stays fast for lists with
+/// thousands of networks, like the published ranges of CDNs and cloud providers.
+///
+/// The list is kept normalized, duplicate networks are removed, networks that are covered by another network are
+/// dropped and adjacent or overlapping networks are merged into the smallest set of networks covering the same addresses.
+///
+/// Besides networks provided by the user, this offers presets for common setups. Lists can be combined with
+/// [`TrustedProxies::with`] and passed to [`RealIpConfigBuilder::trusted_proxies`](crate::RealIpConfigBuilder::trusted_proxies).
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{IpNet, RealIpConfig, TrustedProxies};
+/// #
+/// let proxies = TrustedProxies::loopback()
+///     .with(TrustedProxies::private())
+///     .network(IpNet::new_assert(IpAddr::from([203, 0, 113, 0]), 24));
+///
+/// let config = RealIpConfig::builder().trusted_proxies(proxies).build();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([127, 0, 0, 1])));
+/// ```
+///
+/// Lists can also be parsed from a comma or whitespace separated list of addresses and networks in cidr notation,
+/// or collected from any iterator of networks.
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::{IpNet, ParseTrustedProxiesError, TrustedProxies};
+/// #
+/// let mut proxies: TrustedProxies = "10.0.0.0/8, 192.168.1.1 fc00::/7".parse()?;
+/// assert!(proxies.contains(IpAddr::from([10, 1, 2, 3])));
+/// assert!(!proxies.contains(IpAddr::from([203, 0, 113, 10])));
+///
+/// proxies.extend([IpNet::new_assert(IpAddr::from([203, 0, 113, 0]), 24)]);
+/// assert!(proxies.contains(IpAddr::from([203, 0, 113, 10])));
+///
+/// let proxies: TrustedProxies = [IpNet::from(IpAddr::from([127, 0, 0, 1]))].into_iter().collect();
+/// assert_eq!(&[IpNet::from(IpAddr::from([127, 0, 0, 1]))], proxies.networks());
+///
+/// let proxies: TrustedProxies = "10.0.0.0/24, 10.0.1.0/24, 10.0.1.128/25, 10.0.0.1".parse()?;
+/// assert_eq!(&[IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 23)], proxies.networks());
+/// assert_eq!("10.0.0.0/23".parse::<TrustedProxies>()?, proxies);
+///
+/// assert_eq!(
+///     Err(ParseTrustedProxiesError { index: 1, offset: 11, entry: "10.0.0.0/33".into() }),
+///     "127.0.0.1, 10.0.0.0/33".parse::<TrustedProxies>()
+/// );
+/// # Ok::<(), ParseTrustedProxiesError>(())
+/// ```
+///
+/// With the `serde` feature lists are serialized as a list of networks in cidr notation,
+/// and can be deserialized from a list or from a comma or whitespace separated string.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// # use std::net::IpAddr;
+/// # use real_ip::TrustedProxies;
+/// #
+/// let proxies: TrustedProxies = serde_json::from_str(r#"["10.0.0.0/8", "203.0.113.10"]"#).unwrap();
+/// assert_eq!(r#"["10.0.0.0/8","203.0.113.10/32"]"#, serde_json::to_string(&proxies).unwrap());
+///
+/// let proxies: TrustedProxies = serde_json::from_str(r#""10.0.0.0/8, 203.0.113.10""#).unwrap();
+/// assert!(proxies.contains(IpAddr::from([203, 0, 113, 10])));
+///
+/// assert!(serde_json::from_str::<TrustedProxies>(r#"["10.0.0.0/8", "example.com"]"#).is_err());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<IpNet>,
+    trie: PrefixTrie,
+}
+
+impl PartialEq for TrustedProxies {
+    /// Lists are equal when they contain the same networks, the lookup trie still holds the networks as added
+    fn eq(&self, other: &Self) -> bool {
+        self.networks == other.networks
+    }
+}
+
+impl TrustedProxies {
+    /// An empty list
+    pub fn new() -> Self {
+        TrustedProxies::default()
+    }
+
+    /// The private networks, `10.0.0.0/8`, `172.16.0.0/12`, `192.168.0.0/16` and `fc00::/7`
+    pub fn private() -> Self {
+        Self::preset(&PRIVATE)
+    }
+
+    /// The loopback networks, `127.0.0.0/8` and `::1/128`
+    pub fn loopback() -> Self {
+        Self::preset(&LOOPBACK)
+    }
+
+    /// The link-local networks, `169.254.0.0/16` and `fe80::/10`
+    pub fn link_local() -> Self {
+        Self::preset(&LINK_LOCAL)
+    }
+
+    /// The shared address space for carrier-grade nat, `100.64.0.0/10`
+    pub fn carrier_grade_nat() -> Self {
+        Self::preset(&CARRIER_GRADE_NAT)
+    }
+
+    /// The ranges of Cloudflare's edge servers, as published on <https://www.cloudflare.com/ips/>
+    ///
+    /// The ranges are compiled in and only change with new releases of this crate,
+    /// use `RangeFeed::Cloudflare` from the `fetch` feature to download the current ranges at runtime.
+    ///
+    /// Requires the `preset-cloudflare` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "preset-cloudflare")]
+    /// # {
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{RealIpConfig, TrustedProxies};
+    /// #
+    /// let config = RealIpConfig::builder().trusted_proxies(TrustedProxies::cloudflare()).build();
+    ///
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), config.resolve(&request, IpAddr::from([104, 16, 0, 1])));
+    /// # }
+    /// ```
+    #[cfg(feature = "preset-cloudflare")]
+    pub fn cloudflare() -> Self {
+        Self::preset(&CLOUDFLARE)
+    }
+
+    /// The ranges of Fastly's edge servers, as published on <https://api.fastly.com/public-ip-list>
+    ///
+    /// The ranges are compiled in and only change with new releases of this crate,
+    /// use `RangeFeed::Fastly` from the `fetch` feature to download the current ranges at runtime.
+    ///
+    /// Requires the `preset-fastly` feature.
+    #[cfg(feature = "preset-fastly")]
+    pub fn fastly() -> Self {
+        Self::preset(&FASTLY)
+    }
+
+    /// The ranges Google Cloud's application load balancers and Cloud CDN connect to backends from,
+    /// `35.191.0.0/16` and `130.211.0.0/22`
+    ///
+    /// This covers the load balancers in front of GKE, Cloud Run and Compute Engine backends.
+    /// The ranges are compiled in, `RangeFeed::Google` from the `fetch` feature downloads all of Google's ranges instead.
+    ///
+    /// Requires the `preset-google-cloud` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "preset-google-cloud")]
+    /// # {
+    /// # use std::net::IpAddr;
+    /// # use real_ip::TrustedProxies;
+    /// #
+    /// let proxies = TrustedProxies::google_cloud_load_balancer();
+    /// assert!(proxies.contains(IpAddr::from([35, 191, 10, 1])));
+    /// assert!(proxies.contains(IpAddr::from([130, 211, 3, 1])));
+    /// assert!(!proxies.contains(IpAddr::from([130, 211, 4, 1])));
+    /// # }
+    /// ```
+    #[cfg(feature = "preset-google-cloud")]
+    pub fn google_cloud_load_balancer() -> Self {
+        Self::preset(&GOOGLE_CLOUD_LOAD_BALANCER)
+    }
+
+    /// The ranges Azure Front Door connects to backends from, `147.243.0.0/16` and `2a01:111:2050::/44`
+    ///
+    /// The ranges are shared by all Front Door profiles, so backends should also check the `X-Azure-FDID` header
+    /// to only accept requests from their own profile.
+    /// Application Gateway connects from the addresses of its own subnet in the virtual network,
+    /// which can be trusted with [`TrustedProxies::network`] or [`TrustedProxies::private`].
+    ///
+    /// The ranges are compiled in, use `RangeFeed::AzureServiceTag` from the `fetch` feature
+    /// to download the current ranges at runtime.
+    ///
+    /// Requires the `preset-azure` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "preset-azure")]
+    /// # {
+    /// # use std::net::IpAddr;
+    /// # use real_ip::TrustedProxies;
+    /// #
+    /// let proxies = TrustedProxies::azure_front_door();
+    /// assert!(proxies.contains(IpAddr::from([147, 243, 1, 1])));
+    /// assert!(proxies.contains("2a01:111:2050::1".parse().unwrap()));
+    /// # }
+    /// ```
+    #[cfg(feature = "preset-azure")]
+    pub fn azure_front_door() -> Self {
+        Self::preset(&AZURE_FRONT_DOOR)
+    }
+
+    fn preset(networks: &Networks) -> Self {
+        networks.to_ipnets().collect()
+    }
+
+    /// Add a network to the list
+    pub fn network(mut self, network: IpNet) -> Self {
+        self.push(network);
+        self.aggregate();
+        self
+    }
+
+    /// Add all networks from another list
+    pub fn with(mut self, other: TrustedProxies) -> Self {
+        self.extend(other.networks);
+        self
+    }
+
+    /// Load a list from a file with an address or network in cidr notation on every line.
+    ///
+    /// Empty lines and comments starting with `#` are ignored. A file with an invalid entry fails to load with
+    /// [`io::ErrorKind::InvalidData`], wrapping a [`ParseTrustedProxiesError`] with the position of the entry in the file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::net::IpAddr;
+    /// # use real_ip::TrustedProxies;
+    /// #
+    /// let path = std::env::temp_dir().join("real-ip-trusted-proxies-example.txt");
+    /// std::fs::write(&path, "# load balancers\n10.0.0.0/8\n\n203.0.113.10 # edge proxy\n")?;
+    ///
+    /// let proxies = TrustedProxies::from_file(&path)?;
+    /// assert!(proxies.contains(IpAddr::from([10, 1, 2, 3])));
+    /// assert!(proxies.contains(IpAddr::from([203, 0, 113, 10])));
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Parse a comma or whitespace separated list of addresses and networks in cidr notation,
+    /// reporting every invalid entry instead of only the first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use real_ip::TrustedProxies;
+    /// #
+    /// let err = TrustedProxies::parse_list("10.0.0.0/8, 192.168.1.300, fd00::/8, example.com").unwrap_err();
+    /// assert_eq!(
+    ///     vec![(1, 12, "192.168.1.300"), (3, 37, "example.com")],
+    ///     err.0.iter().map(|err| (err.index, err.offset, err.entry.as_str())).collect::<Vec<_>>()
+    /// );
+    /// assert_eq!(
+    ///     "invalid trusted proxies: invalid trusted proxy 192.168.1.300 at index 1, invalid trusted proxy example.com at index 3",
+    ///     err.to_string()
+    /// );
+    /// ```
+    pub fn parse_list(s: &str) -> Result<Self, InvalidTrustedProxies> {
+        let mut proxies = TrustedProxies::new();
+        let mut errors = Vec::new();
+        for entry in parse_entries(s) {
+            match entry {
+                Ok(network) => proxies.push(network),
+                Err(err) => errors.push(err),
+            }
+        }
+        if errors.is_empty() {
+            proxies.aggregate();
+            Ok(proxies)
+        } else {
+            Err(InvalidTrustedProxies(errors))
+        }
+    }
+
+    /// Load a list from an environment variable containing a comma or whitespace separated list of addresses
+    /// and networks in cidr notation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ParseTrustedProxiesError, TrustedProxies, TrustedProxiesEnvError};
+    /// #
+    /// std::env::set_var("EXAMPLE_TRUSTED_PROXIES", "10.0.0.0/8, 203.0.113.10");
+    /// let proxies = TrustedProxies::from_env("EXAMPLE_TRUSTED_PROXIES")?;
+    /// assert!(proxies.contains(IpAddr::from([203, 0, 113, 10])));
+    ///
+    /// std::env::set_var("EXAMPLE_TRUSTED_PROXIES", "10.0.0.0/8, 203.0.113.300");
+    /// let err = TrustedProxies::from_env("EXAMPLE_TRUSTED_PROXIES").unwrap_err();
+    /// assert_eq!(
+    ///     "invalid value for environment variable EXAMPLE_TRUSTED_PROXIES: invalid trusted proxy 203.0.113.300 at index 1",
+    ///     err.to_string()
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Err(TrustedProxiesEnvError::NotPresent("EXAMPLE_UNSET_PROXIES".into())),
+    ///     TrustedProxies::from_env("EXAMPLE_UNSET_PROXIES")
+    /// );
+    /// # Ok::<(), TrustedProxiesEnvError>(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_env(variable: &str) -> Result<Self, TrustedProxiesEnvError> {
+        match env::var(variable) {
+            Ok(value) => value
+                .parse()
+                .map_err(|error| TrustedProxiesEnvError::Invalid {
+                    variable: variable.into(),
+                    error,
+                }),
+            Err(env::VarError::NotPresent) => {
+                Err(TrustedProxiesEnvError::NotPresent(variable.into()))
+            }
+            Err(env::VarError::NotUnicode(_)) => {
+                Err(TrustedProxiesEnvError::NotUnicode(variable.into()))
+            }
+        }
+    }
+
+    fn push(&mut self, network: IpNet) {
+        self.networks.push(network);
+        self.trie.insert(network);
+    }
+
+    /// Merge the networks into the smallest list covering the same addresses
+    fn aggregate(&mut self) {
+        self.networks = IpNet::aggregate(&self.networks);
+    }
+
+    /// The networks in the list
+    pub fn networks(&self) -> &[IpNet] {
+        &self.networks
+    }
+
+    /// Check if an address is within one of the networks, see [`is_trusted_proxy`](crate::is_trusted_proxy)
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.trie.contains(ip)
+    }
+}
+
+impl TrustPolicy for TrustedProxies {
+    fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+        self.contains(ip)
+    }
+}
+
+impl IntoIterator for TrustedProxies {
+    type Item = IpNet;
+    type IntoIter = alloc::vec::IntoIter<IpNet>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.networks.into_iter()
+    }
+}
+
+impl From<Vec<IpNet>> for TrustedProxies {
+    fn from(networks: Vec<IpNet>) -> Self {
+        networks.into_iter().collect()
+    }
+}
+
+impl<N: Network> FromIterator<N> for TrustedProxies {
+    fn from_iter<T: IntoIterator<Item = N>>(iter: T) -> Self {
+        let mut proxies = TrustedProxies::default();
+        proxies.extend(iter);
+        proxies
+    }
+}
+
+impl<N: Network> Extend<N> for TrustedProxies {
+    fn extend<T: IntoIterator<Item = N>>(&mut self, iter: T) {
+        for network in iter {
+            self.push(network.to_ipnet());
+        }
+        self.aggregate();
+    }
+}
+
+impl FromStr for TrustedProxies {
+    type Err = ParseTrustedProxiesError;
+
+    /// Parse a comma or whitespace separated list of addresses and networks in cidr notation,
+    /// ignoring comments starting with `#`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_entries(s).collect()
+    }
+}
+
+/// Parse the entries of a list of trusted proxies
+fn parse_entries(s: &str) -> impl Iterator<Item = Result<IpNet, ParseTrustedProxiesError>> + '_ {
+    s.lines()
+        .flat_map(|line| {
+            let entries = line.split('#').next().unwrap_or_default();
+            entries.split(|c: char| c == ',' || c.is_whitespace())
+        })
+        .filter(|entry| !entry.is_empty())
+        .enumerate()
+        .map(move |(index, entry)| {
+            IpNet::from_str(entry)
+                .or_else(|_| IpAddr::from_str(entry).map(IpNet::from))
+                .map_err(|_| ParseTrustedProxiesError {
+                    index,
+                    offset: entry.as_ptr() as usize - s.as_ptr() as usize,
+                    entry: entry.into(),
+                })
+        })
+}
+
+/// Error returned when parsing an invalid list of trusted proxies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTrustedProxiesError {
+    /// The index of the invalid entry in the list, not counting empty entries
+    pub index: usize,
+    /// The byte offset of the invalid entry in the parsed string
+    pub offset: usize,
+    /// The entry that isn't a valid address or network
+    pub entry: String,
+}
+
+impl Display for ParseTrustedProxiesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "invalid trusted proxy {} at index {}",
+            self.entry, self.index
+        )
+    }
+}
+
+impl Error for ParseTrustedProxiesError {}
+
+/// Error returned by [`TrustedProxies::parse_list`], containing every invalid entry of the list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTrustedProxies(pub Vec<ParseTrustedProxiesError>);
+
+impl Display for InvalidTrustedProxies {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("invalid trusted proxies: ")?;
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidTrustedProxies {}
+
+/// Error returned when loading trusted proxies from an environment variable, see [`TrustedProxies::from_env`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustedProxiesEnvError {
+    /// The environment variable isn't set
+    NotPresent(String),
+    /// The value of the environment variable isn't valid unicode
+    NotUnicode(String),
+    /// The environment variable contains an entry that isn't a valid address or network
+    Invalid {
+        variable: String,
+        error: ParseTrustedProxiesError,
+    },
+}
+
+#[cfg(feature = "std")]
+impl Display for TrustedProxiesEnvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TrustedProxiesEnvError::NotPresent(variable) => {
+                write!(f, "environment variable {} is not set", variable)
+            }
+            TrustedProxiesEnvError::NotUnicode(variable) => {
+                write!(f, "environment variable {} is not valid unicode", variable)
+            }
+            TrustedProxiesEnvError::Invalid { variable, error } => {
+                write!(
+                    f,
+                    "invalid value for environment variable {}: {}",
+                    variable, error
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TrustedProxiesEnvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TrustedProxiesEnvError::Invalid { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use core::net::{IpAddr, SocketAddr};
+
+/// The address of the peer that connected to the server, usually the closest reverse proxy
+///
+/// This can be created from both an [`IpAddr`] and a [`SocketAddr`], the port of a socket address is kept and
+/// reported as the port of the last hop in the chain.
+///
+/// For requests arriving over a unix domain socket, use [`RemoteAddr::unix_socket`].
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use real_ip::RemoteAddr;
+/// #
+/// let remote = RemoteAddr::from(SocketAddr::from(([10, 0, 0, 1], 36012)));
+/// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), remote.ip());
+/// assert_eq!(Some(36012), remote.port());
+///
+/// let remote = RemoteAddr::from(IpAddr::from([10, 0, 0, 1]));
+/// assert_eq!(None, remote.port());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RemoteAddr {
+    /// `None` for unix sockets
+    ip: Option<IpAddr>,
+    port: Option<u16>,
+}
+
+impl RemoteAddr {
+    /// Create a remote address from an ip address and optional port
+    pub fn new(ip: IpAddr, port: Option<u16>) -> Self {
+        RemoteAddr { ip: Some(ip), port }
+    }
+
+    /// The remote for a request that arrived over a unix domain socket
+    ///
+    /// Only a local process can connect to a unix socket, so the connected proxy is implicitly trusted and the
+    /// forwarding headers it sets are honored. The chain of such a request doesn't contain a remote hop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{real_ip, RemoteAddr};
+    /// #
+    /// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), real_ip(request.headers(), RemoteAddr::unix_socket(), &[]));
+    ///
+    /// let request = Request::builder().body(()).unwrap();
+    /// assert_eq!(None, real_ip(request.headers(), RemoteAddr::unix_socket(), &[]));
+    /// ```
+    pub fn unix_socket() -> Self {
+        RemoteAddr {
+            ip: None,
+            port: None,
+        }
+    }
+
+    /// The ip address of the remote, `None` for unix sockets
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.ip
+    }
+
+    /// Whether the request arrived over a unix domain socket
+    pub fn is_unix_socket(&self) -> bool {
+        self.ip.is_none()
+    }
+
+    /// The port of the remote, if it is known
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+}
+
+impl From<IpAddr> for RemoteAddr {
+    fn from(ip: IpAddr) -> Self {
+        RemoteAddr {
+            ip: Some(ip),
+            port: None,
+        }
+    }
+}
+
+impl From<SocketAddr> for RemoteAddr {
+    fn from(addr: SocketAddr) -> Self {
+        RemoteAddr {
+            ip: Some(addr.ip()),
+            port: Some(addr.port()),
+        }
+    }
+}
+
+/// Information about the connection a request arrived on
+///
+/// Besides the remote address, this contains details about the listener that accepted the connection which can be
+/// used to only honor forwarding headers on specific listeners,
+/// see [`RealIpConfigBuilder::trusted_listener`](crate::RealIpConfigBuilder::trusted_listener) and
+/// [`RealIpConfigBuilder::require_tls`](crate::RealIpConfigBuilder::require_tls).
+///
+/// A connection can be created from anything that can be converted into a [`RemoteAddr`], in which case the local
+/// address is unknown and the connection is assumed to be unencrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionInfo {
+    /// The address of the peer that connected to the server
+    pub remote: RemoteAddr,
+    /// The local address of the listener that accepted the connection, if known
+    pub local_addr: Option<SocketAddr>,
+    /// Whether the connection is encrypted with tls
+    pub tls: bool,
+}
+
+impl From<RemoteAddr> for ConnectionInfo {
+    fn from(remote: RemoteAddr) -> Self {
+        ConnectionInfo {
+            remote,
+            local_addr: None,
+            tls: false,
+        }
+    }
+}
+
+impl From<IpAddr> for ConnectionInfo {
+    fn from(ip: IpAddr) -> Self {
+        RemoteAddr::from(ip).into()
+    }
+}
+
+impl From<SocketAddr> for ConnectionInfo {
+    fn from(addr: SocketAddr) -> Self {
+        RemoteAddr::from(addr).into()
+    }
+}
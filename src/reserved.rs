@@ -0,0 +1,151 @@
+#[cfg(feature = "std")]
+use core::net::IpAddr;
+use core::net::{Ipv4Addr, Ipv6Addr};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+type V4Networks = &'static [([u8; 4], u8)];
+type V6Networks = &'static [([u16; 8], u8)];
+
+/// A static list of IPv4 and IPv6 networks
+pub(crate) struct Networks {
+    pub(crate) v4: V4Networks,
+    pub(crate) v6: V6Networks,
+}
+
+pub(crate) const PRIVATE: Networks = Networks {
+    v4: &[
+        ([10, 0, 0, 0], 8),
+        ([172, 16, 0, 0], 12),
+        ([192, 168, 0, 0], 16),
+    ],
+    v6: &[([0xfc00, 0, 0, 0, 0, 0, 0, 0], 7)],
+};
+pub(crate) const LOOPBACK: Networks = Networks {
+    v4: &[([127, 0, 0, 0], 8)],
+    v6: &[([0, 0, 0, 0, 0, 0, 0, 1], 128)],
+};
+pub(crate) const LINK_LOCAL: Networks = Networks {
+    v4: &[([169, 254, 0, 0], 16)],
+    v6: &[([0xfe80, 0, 0, 0, 0, 0, 0, 0], 10)],
+};
+pub(crate) const CARRIER_GRADE_NAT: Networks = Networks {
+    v4: &[([100, 64, 0, 0], 10)],
+    v6: &[],
+};
+
+impl Networks {
+    /// The networks as [`IpNet`]s
+    pub(crate) fn to_ipnets(&self) -> impl Iterator<Item = IpNet> {
+        let v4 = self
+            .v4
+            .iter()
+            .map(|(addr, prefix)| Ipv4Net::new_assert(Ipv4Addr::from(*addr), *prefix).into());
+        let v6 = self
+            .v6
+            .iter()
+            .map(|(addr, prefix)| Ipv6Net::new_assert(Ipv6Addr::from(*addr), *prefix).into());
+        v4.chain(v6)
+    }
+}
+
+/// Networks for addresses that are only used within a private network
+#[cfg(feature = "std")]
+const PRIVATE_NETWORKS: [Networks; 4] = [PRIVATE, LOOPBACK, LINK_LOCAL, CARRIER_GRADE_NAT];
+
+/// Other IPv4 networks that aren't used by clients on the public internet
+#[cfg(feature = "std")]
+const RESERVED_V4: V4Networks = &[
+    ([0, 0, 0, 0], 8),       // "this" network
+    ([192, 0, 0, 0], 24),    // protocol assignments
+    ([192, 0, 2, 0], 24),    // documentation
+    ([198, 18, 0, 0], 15),   // benchmarking
+    ([198, 51, 100, 0], 24), // documentation
+    ([203, 0, 113, 0], 24),  // documentation
+    ([224, 0, 0, 0], 4),     // multicast
+    ([240, 0, 0, 0], 4),     // reserved, including broadcast
+];
+
+/// Other IPv6 networks that aren't used by clients on the public internet
+#[cfg(feature = "std")]
+const RESERVED_V6: V6Networks = &[
+    ([0, 0, 0, 0, 0, 0, 0, 0], 128),         // unspecified
+    ([0x64, 0xff9b, 1, 0, 0, 0, 0, 0], 48),  // local nat64
+    ([0x100, 0, 0, 0, 0, 0, 0, 0], 64),      // discard
+    ([0x2001, 0xdb8, 0, 0, 0, 0, 0, 0], 32), // documentation
+    ([0xff00, 0, 0, 0, 0, 0, 0, 0], 8),      // multicast
+];
+
+/// Check if an address is in private, loopback, link-local or carrier-grade nat address space
+#[cfg(feature = "std")]
+pub(crate) fn is_private(ip: IpAddr) -> bool {
+    PRIVATE_NETWORKS.iter().any(|networks| match ip {
+        IpAddr::V4(ip) => contains_v4(networks.v4, ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => contains_v4(networks.v4, ip),
+            None => contains_v6(networks.v6, ip),
+        },
+    })
+}
+
+/// Check if an address is in private or other reserved address space
+#[cfg(feature = "std")]
+pub(crate) fn is_reserved(ip: IpAddr) -> bool {
+    is_private(ip)
+        || match ip {
+            IpAddr::V4(ip) => contains_v4(RESERVED_V4, ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => contains_v4(RESERVED_V4, ip),
+                None => contains_v6(RESERVED_V6, ip),
+            },
+        }
+}
+
+/// Check if a network lies entirely within private or other reserved address space
+#[cfg(feature = "std")]
+pub(crate) fn is_reserved_network(network: IpNet) -> bool {
+    match network {
+        IpNet::V4(network) => PRIVATE_NETWORKS
+            .iter()
+            .map(|networks| networks.v4)
+            .chain([RESERVED_V4])
+            .flatten()
+            .any(|(addr, prefix)| {
+                Ipv4Net::new_assert(Ipv4Addr::from(*addr), *prefix).contains(&network)
+            }),
+        IpNet::V6(network) => PRIVATE_NETWORKS
+            .iter()
+            .map(|networks| networks.v6)
+            .chain([RESERVED_V6])
+            .flatten()
+            .any(|(addr, prefix)| {
+                Ipv6Net::new_assert(Ipv6Addr::from(*addr), *prefix).contains(&network)
+            }),
+    }
+}
+
+/// Check if an address is internal the way Apache's `mod_remoteip` defines it, an address in the IPv4 private networks
+/// or an IPv6 address outside of global unicast `2000::/3`
+#[cfg(feature = "std")]
+pub(crate) fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => contains_v4(PRIVATE.v4, ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => contains_v4(PRIVATE.v4, ip),
+            None => ip.segments()[0] & 0xe000 != 0x2000,
+        },
+    }
+}
+
+#[cfg(feature = "std")]
+fn contains_v4(networks: V4Networks, ip: Ipv4Addr) -> bool {
+    networks
+        .iter()
+        .any(|(addr, prefix)| Ipv4Net::new_assert(Ipv4Addr::from(*addr), *prefix).contains(&ip))
+}
+
+#[cfg(feature = "std")]
+fn contains_v6(networks: V6Networks, ip: Ipv6Addr) -> bool {
+    networks
+        .iter()
+        .any(|(addr, prefix)| Ipv6Net::new_assert(Ipv6Addr::from(*addr), *prefix).contains(&ip))
+}
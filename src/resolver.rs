@@ -0,0 +1,248 @@
+use crate::chain::Chain;
+use crate::source::cached_decision;
+use crate::{
+    ClientAddr, ConnectionInfo, Decision, ForwardedContext, Hop, RealIpConfig, RealIpError,
+    RealIpSource, RequestExtensions, TrustPolicy,
+};
+use http::HeaderMap;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// A reusable resolver for the "real-ip" of incoming requests.
+///
+/// The trusted proxies are pre-processed once when creating the resolver into sorted ranges of addresses,
+/// so every hop is checked with a binary search. This makes it more efficient than [`real_ip`](crate::real_ip)
+/// when handling a large number of requests or trusted proxies.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::{IpNet, Resolver};
+/// #
+/// let resolver = Resolver::new([
+///     IpAddr::from([10, 0, 0, 1]).into(),
+///     IpNet::new_assert(IpAddr::from([10, 10, 10, 0]), 24),
+/// ]);
+///
+/// let incoming_ip = IpAddr::from([10, 0, 0, 1]);
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 10.10.10.10").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolver.resolve(&request, incoming_ip));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    config: RealIpConfig,
+    trusted: TrustedNetworks,
+}
+
+impl Resolver {
+    /// Create a resolver with the default configuration for the provided trusted proxies
+    pub fn new(trusted_proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        RealIpConfig::builder()
+            .trusted_proxies(trusted_proxies)
+            .build()
+            .into()
+    }
+
+    /// The configuration used by this resolver
+    pub fn config(&self) -> &RealIpConfig {
+        &self.config
+    }
+
+    /// Get the "real-ip" of an incoming request.
+    pub fn resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.chain(request, connection).resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request from its headers.
+    pub fn resolve_headers(
+        &self,
+        headers: &HeaderMap,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.chain(headers, connection).resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request, including the client port if it's provided by the forwarding header.
+    pub fn resolve_with_port(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ClientAddr> {
+        self.chain(request, connection)
+            .resolve_hop()
+            .map(Hop::client_addr)
+    }
+
+    /// Get the "real-ip" of an incoming request, or the reason it couldn't be determined.
+    ///
+    /// See [`try_real_ip`](crate::try_real_ip) for details on when this fails.
+    pub fn try_resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Result<IpAddr, RealIpError> {
+        self.chain(request, connection).try_resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request, with a report of how it was determined.
+    pub fn resolve_with_report(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Decision {
+        self.chain(request, connection).into_decision(request)
+    }
+
+    /// Get the report of how the "real-ip" of an incoming request was determined, caching it in the
+    /// [`Extensions`](http::Extensions) of the request.
+    ///
+    /// When a decision is already cached in the request it is returned without parsing the headers again,
+    /// even if it was determined by a different configuration.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{Decision, Resolver};
+    /// #
+    /// let resolver = Resolver::new([IpAddr::from([10, 0, 0, 1]).into()]);
+    ///
+    /// let mut request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+    /// let decision = resolver.resolve_cached(&mut request, IpAddr::from([10, 0, 0, 1]));
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), decision.ip);
+    ///
+    /// // later handlers can use the cached decision
+    /// let decision = Decision::cached(request.extensions()).unwrap();
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), decision.ip);
+    /// ```
+    pub fn resolve_cached<'r, R: RequestExtensions>(
+        &self,
+        request: &'r mut R,
+        connection: impl Into<ConnectionInfo>,
+    ) -> &'r Decision {
+        cached_decision(request, |request| {
+            self.resolve_with_report(request, connection)
+        })
+    }
+
+    /// Get the "real-ip" of an incoming request, together with the protocol, host and proxies reported by the
+    /// forwarding headers.
+    pub fn resolve_context(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ForwardedContext> {
+        self.chain(request, connection).into_context()
+    }
+
+    fn chain<'a, S: RealIpSource + ?Sized>(
+        &'a self,
+        source: &'a S,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Chain<'a> {
+        let trust: &dyn TrustPolicy = match self.config.trust_policy() {
+            Some(policy) => policy,
+            None => &self.trusted,
+        };
+        Chain::new(&self.config, source, connection.into(), trust)
+    }
+}
+
+impl From<RealIpConfig> for Resolver {
+    fn from(config: RealIpConfig) -> Self {
+        let trusted = TrustedNetworks {
+            trusted: NetworkSet::new(config.trusted_proxies()),
+            excluded: NetworkSet::new(config.excluded_proxies()),
+        };
+        Resolver { config, trusted }
+    }
+}
+
+/// The trusted networks, without the excluded networks
+#[derive(Debug, Clone, Default)]
+struct TrustedNetworks {
+    trusted: NetworkSet,
+    excluded: NetworkSet,
+}
+
+impl TrustPolicy for TrustedNetworks {
+    fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+        self.trusted.contains(ip) && !self.excluded.contains(ip)
+    }
+}
+
+/// Networks split by address family, as sorted non-overlapping ranges of addresses
+#[derive(Debug, Clone, Default)]
+struct NetworkSet {
+    v4: Intervals,
+    v6: Intervals,
+}
+
+impl NetworkSet {
+    fn new(networks: &[IpNet]) -> Self {
+        let mut v4 = Vec::new();
+        let mut v6 = Vec::new();
+        for network in networks {
+            match network {
+                IpNet::V4(net) => v4.push((
+                    u32::from(net.network()).into(),
+                    u32::from(net.broadcast()).into(),
+                )),
+                IpNet::V6(net) => v6.push((net.network().into(), net.broadcast().into())),
+            }
+        }
+        NetworkSet {
+            v4: Intervals::new(v4),
+            v6: Intervals::new(v6),
+        }
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => self.v4.contains(u32::from(ip).into()),
+            IpAddr::V6(ip) => {
+                self.v6.contains(ip.into())
+                    || ip
+                        .to_ipv4_mapped()
+                        .is_some_and(|ip| self.v4.contains(u32::from(ip).into()))
+            }
+        }
+    }
+}
+
+/// Sorted, non-overlapping and non-adjacent inclusive ranges, searched with a binary search
+#[derive(Debug, Clone, Default)]
+struct Intervals {
+    ranges: Vec<(u128, u128)>,
+}
+
+impl Intervals {
+    fn new(mut ranges: Vec<(u128, u128)>) -> Self {
+        ranges.sort_unstable();
+        let mut merged: Vec<(u128, u128)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= last_end.saturating_add(1) => {
+                    *last_end = end.max(*last_end);
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        Intervals { ranges: merged }
+    }
+
+    fn contains(&self, value: u128) -> bool {
+        // the first range that ends at or after the value is the only one that can contain it
+        let index = self.ranges.partition_point(|(_, end)| *end < value);
+        self.ranges
+            .get(index)
+            .is_some_and(|(start, _)| *start <= value)
+    }
+}
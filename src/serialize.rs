@@ -0,0 +1,128 @@
+use crate::{ForwardingHeader, TrustedProxies};
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+
+/// Headers are serialized as their lowercase name
+impl Serialize for ForwardingHeader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ForwardingHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(Error::custom)
+    }
+}
+
+/// Trusted proxies are serialized as a list of networks in cidr notation
+impl Serialize for TrustedProxies {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        networks::serialize(self.networks(), serializer)
+    }
+}
+
+/// Trusted proxies are deserialized from a list of addresses and networks,
+/// or from a single comma or whitespace separated string
+impl<'de> Deserialize<'de> for TrustedProxies {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TrustedProxiesVisitor)
+    }
+}
+
+struct TrustedProxiesVisitor;
+
+impl<'de> Visitor<'de> for TrustedProxiesVisitor {
+    type Value = TrustedProxies;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a list of addresses and networks in cidr notation")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        TrustedProxies::parse_list(v).map_err(Error::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut proxies = TrustedProxies::new();
+        while let Some(entry) = seq.next_element::<String>()? {
+            let network = entry
+                .parse::<TrustedProxies>()
+                .map_err(|_| Error::custom(format!("invalid trusted proxy {}", entry)))?;
+            proxies.extend(network);
+        }
+        Ok(proxies)
+    }
+}
+
+/// (De)serialize a list of networks the same way as [`TrustedProxies`]
+pub(crate) mod networks {
+    use crate::TrustedProxies;
+    use ipnet::IpNet;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        networks: &[IpNet],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(networks.iter().map(ToString::to_string))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<IpNet>, D::Error> {
+        Ok(TrustedProxies::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// (De)serialize the proxies of each header as a map from the header name to a list of networks
+pub(crate) mod header_proxies {
+    use crate::{ForwardingHeader, TrustedProxies};
+    use ipnet::IpNet;
+    use serde::de::{MapAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt::Formatter;
+
+    pub(crate) fn serialize<S: Serializer>(
+        headers: &[(ForwardingHeader, Vec<IpNet>)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(headers.iter().map(|(header, proxies)| {
+            (
+                header,
+                proxies.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            )
+        }))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(ForwardingHeader, Vec<IpNet>)>, D::Error> {
+        deserializer.deserialize_map(HeaderProxiesVisitor)
+    }
+
+    struct HeaderProxiesVisitor;
+
+    impl<'de> Visitor<'de> for HeaderProxiesVisitor {
+        type Value = Vec<(ForwardingHeader, Vec<IpNet>)>;
+
+        fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a map of header names to a list of addresses and networks")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut headers = Vec::new();
+            while let Some((header, proxies)) =
+                map.next_entry::<ForwardingHeader, TrustedProxies>()?
+            {
+                headers.push((header, proxies.into_iter().collect()));
+            }
+            Ok(headers)
+        }
+    }
+}
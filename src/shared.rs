@@ -0,0 +1,253 @@
+use crate::{
+    ClientAddr, ConnectionInfo, Decision, ForwardedContext, RealIpConfig, RealIpError,
+    RealIpSource, RequestExtensions, Resolver,
+};
+use arc_swap::ArcSwap;
+use http::HeaderMap;
+use ipnet::IpNet;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A [`Resolver`] that can be replaced at runtime, for example when the trusted proxies change during a deploy.
+///
+/// Resolving a request only loads the current resolver atomically, it never waits for an update.
+/// Requests that are being resolved while the resolver is updated finish with the previous resolver.
+///
+/// Requires the `arc-swap` feature.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use real_ip::SharedResolver;
+/// #
+/// let resolver = SharedResolver::new([IpAddr::from([10, 0, 0, 1]).into()]);
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolver.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+///
+/// // the proxy moved to a new address
+/// resolver.update([IpAddr::from([10, 0, 0, 2]).into()]);
+/// assert_eq!(Some(IpAddr::from([10, 0, 0, 1])), resolver.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolver.resolve(&request, IpAddr::from([10, 0, 0, 2])));
+/// ```
+///
+/// The trusted proxies loaded by `watch_file`, resolved by `trust_hostnames` and downloaded by `refresh_ranges`
+/// are kept separately from the configured trusted proxies, so these can be combined on one resolver.
+/// The resolver trusts the configured proxies and the current networks of every source.
+///
+/// ```rust
+/// # #[cfg(feature = "notify")]
+/// # {
+/// # use http::Request;
+/// # use std::net::IpAddr;
+/// # use std::sync::Arc;
+/// # use real_ip::SharedResolver;
+/// #
+/// let path = std::env::temp_dir().join("real-ip-shared-resolver-example.txt");
+/// std::fs::write(&path, "203.0.113.10").unwrap();
+///
+/// let resolver = Arc::new(SharedResolver::new([]));
+/// let _watcher = resolver.watch_file(&path).unwrap();
+/// resolver.update([IpAddr::from([10, 0, 0, 1]).into()]);
+///
+/// // both the proxy from the file and the configured proxy are trusted
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolver.resolve(&request, IpAddr::from([203, 0, 113, 10])));
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), resolver.resolve(&request, IpAddr::from([10, 0, 0, 1])));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SharedResolver {
+    resolver: ArcSwap<Resolver>,
+    sources: Mutex<TrustSources>,
+}
+
+/// The configured trusted proxies and the networks of every source that refreshes trusted proxies
+#[derive(Debug, Default)]
+struct TrustSources {
+    configured: Vec<IpNet>,
+    sources: BTreeMap<SourceId, Vec<IpNet>>,
+    #[cfg(all(
+        any(feature = "dns", feature = "fetch", feature = "notify"),
+        not(target_family = "wasm")
+    ))]
+    next: usize,
+}
+
+/// A source of trusted proxies of a [`SharedResolver`], like a watched file or a range feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct SourceId(usize);
+
+impl SharedResolver {
+    /// Create a shared resolver with the default configuration for the provided trusted proxies
+    pub fn new(trusted_proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        Resolver::new(trusted_proxies).into()
+    }
+
+    /// The current resolver
+    pub fn load(&self) -> Arc<Resolver> {
+        self.resolver.load_full()
+    }
+
+    /// Replace the configured trusted proxies, keeping the rest of the current configuration
+    ///
+    /// The networks of the sources that refresh trusted proxies are still trusted.
+    pub fn update(&self, trusted_proxies: impl IntoIterator<Item = IpNet>) {
+        let mut sources = self.lock_sources();
+        sources.configured = trusted_proxies.into_iter().collect();
+        let config = self.resolver.load().config().clone();
+        self.store(&sources, config);
+    }
+
+    /// Replace the resolver, including its configuration
+    ///
+    /// The networks of the sources that refresh trusted proxies are still trusted.
+    pub fn replace(&self, resolver: impl Into<Resolver>) {
+        let mut sources = self.lock_sources();
+        let config = resolver.into().config().clone();
+        sources.configured = config.trusted_proxies().to_vec();
+        self.store(&sources, config);
+    }
+
+    fn lock_sources(&self) -> std::sync::MutexGuard<'_, TrustSources> {
+        self.sources.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Store a resolver trusting the configured proxies and the networks of all sources
+    fn store(&self, sources: &TrustSources, mut config: RealIpConfig) {
+        let trusted = sources
+            .configured
+            .iter()
+            .chain(sources.sources.values().flatten())
+            .copied()
+            .collect();
+        config.set_trusted_proxies(trusted);
+        self.resolver.store(Arc::new(config.into()));
+    }
+
+    /// Get the "real-ip" of an incoming request.
+    pub fn resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.resolver.load().resolve(request, connection)
+    }
+
+    /// Get the "real-ip" of an incoming request from its headers.
+    pub fn resolve_headers(
+        &self,
+        headers: &HeaderMap,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<IpAddr> {
+        self.resolver.load().resolve_headers(headers, connection)
+    }
+
+    /// Get the "real-ip" of an incoming request, including the client port if it's provided by the forwarding header.
+    pub fn resolve_with_port(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ClientAddr> {
+        self.resolver.load().resolve_with_port(request, connection)
+    }
+
+    /// Get the "real-ip" of an incoming request, or the reason it couldn't be determined.
+    ///
+    /// See [`try_real_ip`](crate::try_real_ip) for details on when this fails.
+    pub fn try_resolve(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Result<IpAddr, RealIpError> {
+        self.resolver.load().try_resolve(request, connection)
+    }
+
+    /// Get the "real-ip" of an incoming request, with a report of how it was determined.
+    pub fn resolve_with_report(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Decision {
+        self.resolver
+            .load()
+            .resolve_with_report(request, connection)
+    }
+
+    /// Get the report of how the "real-ip" of an incoming request was determined, caching it in the
+    /// extensions of the request, see [`Resolver::resolve_cached`].
+    pub fn resolve_cached<'r, R: RequestExtensions>(
+        &self,
+        request: &'r mut R,
+        connection: impl Into<ConnectionInfo>,
+    ) -> &'r Decision {
+        self.resolver.load().resolve_cached(request, connection)
+    }
+
+    /// Get the "real-ip" of an incoming request, together with the protocol, host and proxies reported by the
+    /// forwarding headers.
+    pub fn resolve_context(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+    ) -> Option<ForwardedContext> {
+        self.resolver.load().resolve_context(request, connection)
+    }
+}
+
+#[cfg(all(
+    any(feature = "dns", feature = "fetch", feature = "notify"),
+    not(target_family = "wasm")
+))]
+impl SharedResolver {
+    /// Register a new source of trusted proxies, without any networks
+    pub(crate) fn add_source(&self) -> SourceId {
+        let mut sources = self.lock_sources();
+        let id = SourceId(sources.next);
+        sources.next += 1;
+        sources.sources.insert(id, Vec::new());
+        id
+    }
+
+    /// Replace the networks of a source of trusted proxies, a source that was removed isn't added again
+    pub(crate) fn update_source(&self, id: SourceId, networks: impl IntoIterator<Item = IpNet>) {
+        let mut sources = self.lock_sources();
+        let Some(current) = sources.sources.get_mut(&id) else {
+            return;
+        };
+        *current = networks.into_iter().collect();
+        let config = self.resolver.load().config().clone();
+        self.store(&sources, config);
+    }
+
+    /// Stop trusting the networks of a source of trusted proxies
+    pub(crate) fn remove_source(&self, id: SourceId) {
+        let mut sources = self.lock_sources();
+        if sources.sources.remove(&id).is_some() {
+            let config = self.resolver.load().config().clone();
+            self.store(&sources, config);
+        }
+    }
+}
+
+impl From<Resolver> for SharedResolver {
+    fn from(resolver: Resolver) -> Self {
+        let sources = TrustSources {
+            configured: resolver.config().trusted_proxies().to_vec(),
+            ..TrustSources::default()
+        };
+        SharedResolver {
+            resolver: ArcSwap::from_pointee(resolver),
+            sources: Mutex::new(sources),
+        }
+    }
+}
+
+impl From<RealIpConfig> for SharedResolver {
+    fn from(config: RealIpConfig) -> Self {
+        Resolver::from(config).into()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::Decision;
+use http::header::ValueIter;
+use http::request::Parts;
+use http::{Extensions, HeaderMap, HeaderValue, Request};
+
+/// A request, or part of a request, that provides the headers needed to determine the "real-ip".
+///
+/// This is implemented for [`Request`], [`Parts`] and [`HeaderMap`] and can be implemented for the native request
+/// types of other frameworks.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::{HeaderMap, HeaderValue, Request};
+/// # use std::net::IpAddr;
+/// # use real_ip::{real_ip, RealIpSource};
+/// #
+/// struct LogEntry {
+///     forwarded_for: Vec<HeaderValue>,
+/// }
+///
+/// impl RealIpSource for LogEntry {
+///     type Values<'a> = std::slice::Iter<'a, HeaderValue>;
+///
+///     fn header<'a>(&'a self, name: &str) -> Self::Values<'a> {
+///         match name {
+///             "x-forwarded-for" => self.forwarded_for.iter(),
+///             _ => [].iter(),
+///         }
+///     }
+/// }
+///
+/// let trusted_proxies = [IpAddr::from([10, 0, 0, 1]).into()];
+/// let entry = LogEntry {
+///     forwarded_for: vec![HeaderValue::from_static("192.0.2.1")],
+/// };
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), real_ip(&entry, IpAddr::from([10, 0, 0, 1]), &trusted_proxies));
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), real_ip(&request, IpAddr::from([10, 0, 0, 1]), &trusted_proxies));
+/// ```
+pub trait RealIpSource {
+    /// The iterator over the values of a header
+    type Values<'a>: DoubleEndedIterator<Item = &'a HeaderValue>
+    where
+        Self: 'a;
+
+    /// Get all values of the header with the provided lowercase name, in the order they appear in the request
+    fn header<'a>(&'a self, name: &str) -> Self::Values<'a>;
+}
+
+impl RealIpSource for HeaderMap {
+    type Values<'a> = ValueIter<'a, HeaderValue>;
+
+    fn header<'a>(&'a self, name: &str) -> Self::Values<'a> {
+        self.get_all(name).iter()
+    }
+}
+
+impl<B> RealIpSource for Request<B> {
+    type Values<'a>
+        = ValueIter<'a, HeaderValue>
+    where
+        B: 'a;
+
+    fn header<'a>(&'a self, name: &str) -> Self::Values<'a> {
+        self.headers().header(name)
+    }
+}
+
+impl RealIpSource for Parts {
+    type Values<'a> = ValueIter<'a, HeaderValue>;
+
+    fn header<'a>(&'a self, name: &str) -> Self::Values<'a> {
+        self.headers.header(name)
+    }
+}
+
+impl<T: RealIpSource + ?Sized> RealIpSource for &T {
+    type Values<'a>
+        = T::Values<'a>
+    where
+        Self: 'a;
+
+    fn header<'a>(&'a self, name: &str) -> Self::Values<'a> {
+        (**self).header(name)
+    }
+}
+
+/// A request that can cache how its "real-ip" was determined in its [`Extensions`],
+/// so middleware, handlers and loggers can share the result without parsing the headers again.
+///
+/// This is implemented for [`Request`] and [`Parts`].
+pub trait RequestExtensions: RealIpSource {
+    /// The extensions of the request
+    fn extensions(&self) -> &Extensions;
+
+    /// The mutable extensions of the request
+    fn extensions_mut(&mut self) -> &mut Extensions;
+}
+
+impl<B> RequestExtensions for Request<B> {
+    fn extensions(&self) -> &Extensions {
+        Request::extensions(self)
+    }
+
+    fn extensions_mut(&mut self) -> &mut Extensions {
+        Request::extensions_mut(self)
+    }
+}
+
+impl RequestExtensions for Parts {
+    fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+}
+
+/// Get the decision cached in the extensions of the request, resolving and caching it if it isn't cached yet
+pub(crate) fn cached_decision<R: RequestExtensions>(
+    request: &mut R,
+    resolve: impl FnOnce(&R) -> Decision,
+) -> &Decision {
+    if request.extensions().get::<Decision>().is_none() {
+        let decision = resolve(request);
+        request.extensions_mut().insert(decision);
+    }
+    let request: &R = request;
+    request
+        .extensions()
+        .get::<Decision>()
+        .expect("decision was cached")
+}
@@ -0,0 +1,158 @@
+use crate::chain::Chain;
+use crate::{ConnectionInfo, Decision, RealIpConfig, RealIpSource, TrustPolicy};
+use std::cell::RefCell;
+use std::future::Future;
+use std::net::IpAddr;
+
+/// An asynchronous [`TrustPolicy`], for trust decisions backed by an external system like a database,
+/// a control-plane api or service discovery.
+///
+/// A store is used with [`RealIpConfig::resolve_async`] and [`RealIpConfig::resolve_with_report_async`],
+/// it takes the place of the configured trusted proxies and trust policy.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::collections::HashSet;
+/// # use std::net::IpAddr;
+/// # use real_ip::{RealIpConfig, TrustStore};
+/// #
+/// struct ServiceDiscovery {
+///     proxies: HashSet<IpAddr>,
+/// }
+///
+/// impl TrustStore for ServiceDiscovery {
+///     async fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+///         // in a real program this would query the service discovery system
+///         self.proxies.contains(&ip)
+///     }
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let store = ServiceDiscovery {
+///     proxies: HashSet::from([IpAddr::from([10, 0, 0, 1])]),
+/// };
+/// let config = RealIpConfig::default();
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1").body(()).unwrap();
+/// let client_ip = config.resolve_async(&request, IpAddr::from([10, 0, 0, 1]), &store).await;
+/// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), client_ip);
+/// # }
+/// ```
+pub trait TrustStore {
+    /// Check if the hop at the given position in the chain is a trusted proxy,
+    /// the position is counted the same as for [`TrustPolicy::is_trusted`]
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> impl Future<Output = bool> + Send;
+}
+
+/// The answers of a trust store for the hops of a chain, recording the hops the chain asks about
+#[derive(Default)]
+struct TrustAnswers {
+    trusted: Vec<(IpAddr, usize)>,
+    queries: RefCell<Vec<(IpAddr, usize)>>,
+}
+
+impl TrustPolicy for TrustAnswers {
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> bool {
+        self.queries.borrow_mut().push((ip, hop_index));
+        self.trusted.contains(&(ip, hop_index))
+    }
+}
+
+impl RealIpConfig {
+    /// Get the "real-ip" of an incoming request, checking the hops with a [`TrustStore`].
+    ///
+    /// The store replaces the trusted proxies and trust policy of the configuration, the other options like the never
+    /// trusted networks and internal proxies still apply. The hops in the forwarding chain are checked with the store
+    /// one after the other starting from the right, until a hop isn't trusted. Addresses a client adds to the chain
+    /// are never looked up.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use http::Request;
+    /// # use std::net::IpAddr;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use real_ip::{RealIpConfig, TrustStore};
+    /// #
+    /// #[derive(Default)]
+    /// struct CountingStore {
+    ///     lookups: AtomicUsize,
+    /// }
+    ///
+    /// impl TrustStore for CountingStore {
+    ///     async fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+    ///         self.lookups.fetch_add(1, Ordering::Relaxed);
+    ///         ip.is_loopback()
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let store = CountingStore::default();
+    /// let request = Request::builder()
+    ///     .header("x-forwarded-for", "198.51.100.1, 198.51.100.2, 198.51.100.3, 192.0.2.1")
+    ///     .body(())
+    ///     .unwrap();
+    /// let client_ip = RealIpConfig::default()
+    ///     .resolve_async(&request, IpAddr::from([127, 0, 0, 1]), &store)
+    ///     .await;
+    /// assert_eq!(Some(IpAddr::from([192, 0, 2, 1])), client_ip);
+    /// // only the remote address and the client are looked up
+    /// assert_eq!(2, store.lookups.load(Ordering::Relaxed));
+    /// # }
+    /// ```
+    pub async fn resolve_async(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+        store: &impl TrustStore,
+    ) -> Option<IpAddr> {
+        let connection = connection.into();
+        let answers = self.query_store(request, connection, store).await;
+        Chain::new(self, request, connection, &answers).resolve()
+    }
+
+    /// Get the "real-ip" of an incoming request with a report of how it was determined,
+    /// checking the hops with a [`TrustStore`].
+    ///
+    /// See [`resolve_async`](Self::resolve_async) for details.
+    pub async fn resolve_with_report_async(
+        &self,
+        request: &impl RealIpSource,
+        connection: impl Into<ConnectionInfo>,
+        store: &impl TrustStore,
+    ) -> Decision {
+        let connection = connection.into();
+        let answers = self.query_store(request, connection, store).await;
+        Chain::new(self, request, connection, &answers).into_decision(request)
+    }
+
+    /// Ask the store about the hops of the chain from the right, until the store doesn't trust a hop
+    ///
+    /// Only the right-most untrusted hop of the chain is looked up, when the chain asked for a trust decision about it.
+    async fn query_store(
+        &self,
+        request: &impl RealIpSource,
+        connection: ConnectionInfo,
+        store: &impl TrustStore,
+    ) -> TrustAnswers {
+        let mut answers = TrustAnswers::default();
+        loop {
+            let hop = Chain::new(self, request, connection, &answers).rightmost_untrusted();
+            let queries = answers.queries.get_mut();
+            let hop = match hop {
+                Some(hop) if queries.contains(&hop) => hop,
+                _ => break,
+            };
+            queries.clear();
+            if !store.is_trusted(hop.0, hop.1).await {
+                break;
+            }
+            answers.trusted.push(hop);
+        }
+        answers
+    }
+}
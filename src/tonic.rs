@@ -0,0 +1,76 @@
+//! Interceptor for [tonic](https://github.com/hyperium/tonic) gRPC services
+//!
+//! Requires the `tonic` feature.
+
+use crate::{ClientIp, Resolver};
+use std::sync::Arc;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+/// An [`Interceptor`] that resolves the "real-ip" of every call and stores it in the request extensions
+///
+/// The forwarding headers are read from the metadata of the call and the remote address is taken from the
+/// transport, calls without a remote address are passed on without resolving their address.
+/// The [`ClientIp`] is stored for the service, together with the [`Decision`](crate::Decision) for anything that
+/// needs the full report.
+///
+/// The interceptor can wrap a single service with `with_interceptor` of the generated server, or all services
+/// with `tonic::service::interceptor` as layer of the server.
+/// With the `tower` feature the [`RealIpLayer`](crate::RealIpLayer) can also be used as layer of the server.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::{IpAddr, SocketAddr};
+/// # use tonic::service::Interceptor;
+/// # use tonic::transport::server::TcpConnectInfo;
+/// # use tonic::Request;
+/// # use real_ip::tonic::RealIpInterceptor;
+/// # use real_ip::{ClientIp, RealIpConfig};
+/// #
+/// let config = RealIpConfig::builder()
+///     .trusted_proxy(IpAddr::from([10, 0, 0, 1]).into())
+///     .build();
+/// let mut interceptor = RealIpInterceptor::new(config);
+///
+/// // the request as received by the tonic transport
+/// let mut request = Request::new(());
+/// request.metadata_mut().insert("x-forwarded-for", "192.0.2.1".parse().unwrap());
+/// request.extensions_mut().insert(TcpConnectInfo {
+///     local_addr: None,
+///     remote_addr: Some(SocketAddr::from(([10, 0, 0, 1], 46532))),
+/// });
+///
+/// let request = interceptor.call(request).unwrap();
+/// assert_eq!(
+///     Some(&ClientIp(IpAddr::from([192, 0, 2, 1]))),
+///     request.extensions().get::<ClientIp>()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RealIpInterceptor {
+    resolver: Arc<Resolver>,
+}
+
+impl RealIpInterceptor {
+    /// Create the interceptor resolving calls with a [`RealIpConfig`](crate::RealIpConfig) or [`Resolver`]
+    pub fn new(resolver: impl Into<Resolver>) -> Self {
+        RealIpInterceptor {
+            resolver: Arc::new(resolver.into()),
+        }
+    }
+}
+
+impl Interceptor for RealIpInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(remote) = request.remote_addr() {
+            let headers = request.metadata().clone().into_headers();
+            let decision = self.resolver.resolve_with_report(&headers, remote);
+            if let Some(ip) = decision.ip {
+                request.extensions_mut().insert(ClientIp(ip));
+            }
+            request.extensions_mut().insert(decision);
+        }
+        Ok(request)
+    }
+}
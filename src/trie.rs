@@ -0,0 +1,78 @@
+use alloc::boxed::Box;
+use core::net::IpAddr;
+use ipnet::IpNet;
+
+/// A binary prefix tree of networks, checking if an address is contained in one of the networks in
+/// time proportional to the number of address bits, regardless of the number of networks.
+///
+/// Matching is the same as [`is_trusted_proxy`](crate::is_trusted_proxy).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PrefixTrie {
+    v4: Node,
+    v6: Node,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Node {
+    /// Whether a network ends at this node, covering every address below it
+    terminal: bool,
+    children: [Option<Box<Node>>; 2],
+}
+
+impl PrefixTrie {
+    pub(crate) fn insert(&mut self, network: IpNet) {
+        match network {
+            IpNet::V4(net) => self
+                .v4
+                .insert(u128::from(u32::from(net.addr())) << 96, net.prefix_len()),
+            IpNet::V6(net) => self.v6.insert(u128::from(net.addr()), net.prefix_len()),
+        }
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => self.v4.contains(u128::from(u32::from(ip)) << 96),
+            IpAddr::V6(ip) => {
+                self.v6.contains(u128::from(ip))
+                    || ip
+                        .to_ipv4_mapped()
+                        .is_some_and(|ip| self.v4.contains(u128::from(u32::from(ip)) << 96))
+            }
+        }
+    }
+}
+
+impl Node {
+    /// Insert a network, with the address bits aligned to the left
+    fn insert(&mut self, bits: u128, prefix_len: u8) {
+        let mut node = self;
+        for depth in 0..prefix_len {
+            if node.terminal {
+                // already covered by a shorter network
+                return;
+            }
+            node = node.children[bit(bits, depth)].get_or_insert_with(Default::default);
+        }
+        node.terminal = true;
+        // the longer networks below this node are redundant now
+        node.children = Default::default();
+    }
+
+    fn contains(&self, bits: u128) -> bool {
+        let mut node = self;
+        for depth in 0..128 {
+            if node.terminal {
+                return true;
+            }
+            match node.children[bit(bits, depth)].as_deref() {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        node.terminal
+    }
+}
+
+fn bit(bits: u128, depth: u8) -> usize {
+    (bits >> (127 - depth) & 1) as usize
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::fmt::{Debug, Formatter};
+use core::net::{IpAddr, Ipv4Addr};
+use ipnet::IpNet;
+
+/// Decides which hops in the forwarding chain are trusted proxies.
+///
+/// The `hop_index` counts from the right of the chain, `0` is the remote address of the request,
+/// `1` the right-most address from the forwarding header, etc.
+///
+/// The trait is implemented for lists of [`IpNet`] and for closures.
+///
+/// # Example
+///
+/// ```rust
+/// # use http::Request;
+/// # use std::net::{IpAddr, Ipv4Addr};
+/// # use real_ip::{real_ip_with_policy, TrustPolicy};
+/// #
+/// // trust the connecting proxy, whatever its address
+/// let policy = |_ip: IpAddr, hop_index: usize| hop_index == 0;
+///
+/// let request = Request::builder().header("x-forwarded-for", "192.0.2.1, 203.0.113.10").body(()).unwrap();
+/// let client_ip = real_ip_with_policy(request.headers(), IpAddr::from([10, 0, 0, 1]), &policy);
+/// assert_eq!(Some(IpAddr::from([203, 0, 113, 10])), client_ip);
+/// ```
+pub trait TrustPolicy {
+    /// Check if the hop at the given position in the chain is a trusted proxy
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> bool;
+}
+
+impl TrustPolicy for [IpNet] {
+    fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+        is_trusted_proxy(ip, self)
+    }
+}
+
+impl<const N: usize> TrustPolicy for [IpNet; N] {
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> bool {
+        self[..].is_trusted(ip, hop_index)
+    }
+}
+
+impl TrustPolicy for Vec<IpNet> {
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> bool {
+        self[..].is_trusted(ip, hop_index)
+    }
+}
+
+impl<F: Fn(IpAddr, usize) -> bool> TrustPolicy for F {
+    fn is_trusted(&self, ip: IpAddr, hop_index: usize) -> bool {
+        self(ip, hop_index)
+    }
+}
+
+/// Check if an address is one of the trusted proxies, using the same matching as [`real_ip`](crate::real_ip).
+///
+/// IPv4-mapped IPv6 addresses, like `::ffff:10.0.0.1`, match the IPv4 networks that contain the mapped address.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::net::IpAddr;
+/// # use real_ip::{is_trusted_proxy, IpNet};
+/// #
+/// let trusted_proxies = [IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8)];
+/// assert!(is_trusted_proxy(IpAddr::from([10, 0, 0, 1]), &trusted_proxies));
+/// assert!(is_trusted_proxy("::ffff:10.0.0.1".parse().unwrap(), &trusted_proxies));
+/// assert!(!is_trusted_proxy(IpAddr::from([192, 0, 2, 1]), &trusted_proxies));
+/// ```
+pub fn is_trusted_proxy(ip: IpAddr, trusted_proxies: &[IpNet]) -> bool {
+    let contains = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(&ip));
+    contains(ip) || ipv4_mapped(ip).is_some_and(|ip| contains(ip.into()))
+}
+
+/// The mapped IPv4 address of an IPv4-mapped IPv6 address
+pub(crate) fn ipv4_mapped(ip: IpAddr) -> Option<Ipv4Addr> {
+    match ip {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped(),
+        IpAddr::V4(_) => None,
+    }
+}
+
+/// A list of trusted proxies, without the excluded networks
+#[cfg(feature = "std")]
+pub(crate) struct ProxyList<'a> {
+    pub(crate) trusted: &'a [IpNet],
+    pub(crate) excluded: &'a [IpNet],
+}
+
+#[cfg(feature = "std")]
+impl TrustPolicy for ProxyList<'_> {
+    fn is_trusted(&self, ip: IpAddr, _hop_index: usize) -> bool {
+        is_trusted_proxy(ip, self.trusted) && !is_trusted_proxy(ip, self.excluded)
+    }
+}
+
+/// A trust policy that can be stored in the configuration
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub(crate) struct SharedTrustPolicy(pub(crate) Arc<dyn TrustPolicy + Send + Sync>);
+
+#[cfg(feature = "std")]
+impl Debug for SharedTrustPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("TrustPolicy")
+    }
+}
@@ -0,0 +1,109 @@
+use crate::reserved::is_reserved_network;
+use crate::{RealIpConfig, Strategy};
+use ipnet::IpNet;
+use std::fmt::{Display, Formatter};
+
+/// A potentially dangerous setup found by [`RealIpConfig::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigWarning {
+    /// A trusted network covers every address, like `0.0.0.0/0` or `::/0`, so every client can spoof its address
+    TrustsEverything(IpNet),
+    /// A trusted network contains public address space
+    ///
+    /// This is expected when trusting the published ranges of a CDN or cloud provider,
+    /// but proxies in the same network as the application are usually in private address space.
+    PublicNetwork(IpNet),
+    /// No proxies are trusted, so the forwarding headers are never used
+    NoTrustedProxies,
+    /// The strategy uses the forwarding header without verifying that it was set by a trusted proxy,
+    /// which is only safe if every request passes through a proxy that sets the header
+    UnverifiedHeader(Strategy),
+}
+
+impl Display for ConfigWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigWarning::TrustsEverything(network) => write!(
+                f,
+                "trusted network {} covers every address, allowing any client to spoof its address",
+                network
+            ),
+            ConfigWarning::PublicNetwork(network) => {
+                write!(f, "trusted network {} contains public addresses", network)
+            }
+            ConfigWarning::NoTrustedProxies => {
+                write!(
+                    f,
+                    "no trusted proxies are configured, forwarding headers are ignored"
+                )
+            }
+            ConfigWarning::UnverifiedHeader(_) => write!(
+                f,
+                "forwarding headers are used without verifying the proxy that set them"
+            ),
+        }
+    }
+}
+
+impl RealIpConfig {
+    /// Check the configuration for setups that are likely to be a mistake or that allow clients to spoof their address.
+    ///
+    /// The warnings don't stop the configuration from being used, they are meant to be logged when the application starts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::net::IpAddr;
+    /// # use real_ip::{ConfigWarning, IpNet, RealIpConfig};
+    /// #
+    /// let config = RealIpConfig::builder()
+    ///     .trusted_proxy(IpNet::new_assert(IpAddr::from([10, 0, 0, 0]), 8))
+    ///     .trusted_proxy("0.0.0.0/0".parse().unwrap())
+    ///     .build();
+    ///
+    /// let warnings = config.validate();
+    /// assert_eq!(vec![ConfigWarning::TrustsEverything("0.0.0.0/0".parse().unwrap())], warnings);
+    /// assert_eq!(
+    ///     "trusted network 0.0.0.0/0 covers every address, allowing any client to spoof its address",
+    ///     warnings[0].to_string()
+    /// );
+    ///
+    /// // the published range of a CDN
+    /// let config = RealIpConfig::builder().trusted_proxy("198.41.128.0/17".parse().unwrap()).build();
+    /// assert_eq!(vec![ConfigWarning::PublicNetwork("198.41.128.0/17".parse().unwrap())], config.validate());
+    ///
+    /// assert_eq!(vec![ConfigWarning::NoTrustedProxies], RealIpConfig::default().validate());
+    /// ```
+    pub fn validate(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+        if self.headers().is_empty() {
+            return warnings;
+        }
+
+        for network in self.trusted_proxies().iter().chain(self.internal_proxies()) {
+            if network.prefix_len() == 0 {
+                warnings.push(ConfigWarning::TrustsEverything(*network));
+            } else if !is_reserved_network(*network) {
+                warnings.push(ConfigWarning::PublicNetwork(*network));
+            }
+        }
+
+        match self.strategy() {
+            Strategy::RightmostUntrusted => {
+                let trusts_proxies = !self.trusted_proxies().is_empty()
+                    || !self.internal_proxies().is_empty()
+                    || self.trust_policy().is_some()
+                    || self.trust_private_hops();
+                if !trusts_proxies {
+                    warnings.push(ConfigWarning::NoTrustedProxies);
+                }
+            }
+            strategy @ (Strategy::LeftmostHeaderValue | Strategy::RightmostHeaderValue) => {
+                warnings.push(ConfigWarning::UnverifiedHeader(strategy));
+            }
+            Strategy::TrustedHopCount(_) => {}
+        }
+        warnings
+    }
+}
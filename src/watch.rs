@@ -0,0 +1,105 @@
+use crate::shared::SourceId;
+use crate::{SharedResolver, TrustedProxies};
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, Weak};
+
+/// Reloads the trusted proxies of a [`SharedResolver`] when a file changes, see [`SharedResolver::watch_file`].
+///
+/// The file is watched until this is dropped, after which the proxies from the file are no longer trusted.
+///
+/// Requires the `notify` feature.
+#[derive(Debug)]
+pub struct ProxyFileWatcher {
+    _watcher: RecommendedWatcher,
+    resolver: Weak<SharedResolver>,
+    source: SourceId,
+}
+
+impl Drop for ProxyFileWatcher {
+    fn drop(&mut self) {
+        if let Some(resolver) = self.resolver.upgrade() {
+            resolver.remove_source(self.source);
+        }
+    }
+}
+
+impl SharedResolver {
+    /// Load the trusted proxies from a file and reload them whenever the file changes.
+    ///
+    /// The file is loaded with [`TrustedProxies::from_file`]. Loading the file when starting to watch it has to succeed,
+    /// when a later version of the file can't be loaded the previous list of trusted proxies is kept.
+    /// The proxies from the file are trusted in addition to the configured trusted proxies and the networks of other
+    /// sources like `refresh_ranges`, reloading the file only replaces the proxies from the
+    /// file.
+    ///
+    /// The directory of the file is watched instead of the file itself,
+    /// so the file can be replaced by configuration management instead of being modified in place.
+    ///
+    /// Requires the `notify` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use std::sync::Arc;
+    /// # use real_ip::SharedResolver;
+    /// #
+    /// let resolver = Arc::new(SharedResolver::new([]));
+    /// let _watcher = resolver.watch_file("/etc/trusted-proxies.txt")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn watch_file(self: &Arc<Self>, path: impl Into<PathBuf>) -> io::Result<ProxyFileWatcher> {
+        let path = path.into();
+        let proxies = TrustedProxies::from_file(&path)?;
+
+        // the proxies are only trusted once the file is watched, changes before that are picked up by loading the
+        // file again after starting the watcher
+        let source = Arc::new(OnceLock::new());
+        let watched_source = Arc::clone(&source);
+        let resolver = Arc::clone(self);
+        let file_name = path.file_name().map(ToOwned::to_owned);
+        let watched = path.clone();
+        let mut watcher = recommended_watcher(move |event: notify::Result<Event>| {
+            let changed = event.is_ok_and(|event| {
+                matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+                ) && event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == file_name.as_deref())
+            });
+            if let (true, Some(source)) = (changed, watched_source.get()) {
+                if let Ok(proxies) = TrustedProxies::from_file(&watched) {
+                    resolver.update_source(*source, proxies);
+                }
+            }
+        })
+        .map_err(watch_error)?;
+
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+
+        let id = self.add_source();
+        source.get_or_init(|| id);
+        self.update_source(id, TrustedProxies::from_file(&path).unwrap_or(proxies));
+        Ok(ProxyFileWatcher {
+            _watcher: watcher,
+            resolver: Arc::downgrade(self),
+            source: id,
+        })
+    }
+}
+
+fn watch_error(err: notify::Error) -> io::Error {
+    match err.kind {
+        notify::ErrorKind::Io(err) => err,
+        _ => io::Error::other(err),
+    }
+}